@@ -0,0 +1,45 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use anyhow::Result;
+use clap::Parser;
+use twg_2024_1::{
+    level_check::{self, Severity},
+    levels::data::load_manifest,
+};
+
+/// Checks level scenes for the node names, extras and component settings the game expects.
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// glTF files, relative to the assets folder unless they exist as given.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Level manifest in the assets folder, used to find the components of each scene.
+    #[arg(long, default_value = "levels.json")]
+    manifest: String,
+}
+
+fn main() -> Result<ExitCode> {
+    let args = Args::parse();
+    let levels = load_manifest(&args.manifest)?;
+    let registered = level_check::scene_components();
+
+    let mut errors = 0;
+    for file in &args.files {
+        let issues = level_check::check(file, &levels, &registered)?;
+        for issue in &issues {
+            println!("{}: {issue}", file.display());
+        }
+        errors += issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .count();
+    }
+
+    println!("{errors} error(s)");
+    Ok(if errors > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
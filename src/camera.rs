@@ -4,6 +4,7 @@ use bevy::prelude::*;
 use crate::{
     handle_errors,
     player::{Player, PlayerPhysics},
+    simulation::Interpolated,
 };
 
 pub struct CameraPlugin;
@@ -21,13 +22,16 @@ impl Plugin for CameraPlugin {
 
 fn update(
     time: Res<Time>,
+    fixed_time: Res<Time<Fixed>>,
     player: Res<Player>,
-    transform: Query<&Transform, (With<PlayerPhysics>, Without<Camera3d>)>,
+    interpolated: Query<&Interpolated, With<PlayerPhysics>>,
     mut cameras: Query<&mut Transform, With<Camera3d>>,
 ) -> Result<()> {
     let mut speed = 10.0 * time.delta_seconds();
 
-    let transform = transform.get_single()?;
+    let transform = interpolated
+        .get_single()?
+        .at(fixed_time.overstep_percentage());
     let lookat = transform.translation.clone();
     let newpos = Vec3::from((
         transform.translation.x - 1.0,
@@ -36,7 +40,7 @@ fn update(
     ));
 
     let mut new_transform = Transform::from_translation(newpos).looking_at(lookat, Vec3::Y);
-    if player.is_space {
+    if player.is_flashlight {
         new_transform.translation.z -= 4.0;
         new_transform.translation.y -= 1.0;
         speed *= 0.5;
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_rapier2d::dynamics::Velocity;
+
+use crate::{
+    components::loading::Loading,
+    player::{Player, PlayerPhysics},
+    sensor::SensorContacts,
+    simulation::{Interpolated, SimulationSet},
+    wiring::{WiringGraph, WiringSet},
+    GameState,
+};
+
+/// Sensor volume marked with the `"checkpoint": true` glTF extra.
+#[derive(Component)]
+pub struct Checkpoint;
+
+/// Component whose state is saved when a checkpoint is reached and restored on respawn.
+pub trait Checkpointed: Component {
+    type Snapshot: Send + Sync + 'static;
+
+    fn snapshot(&self) -> Self::Snapshot;
+    fn restore(&mut self, snapshot: &Self::Snapshot);
+}
+
+#[derive(Event)]
+pub struct CheckpointReached {
+    pub entity: Entity,
+    pub level: GameState,
+}
+
+#[derive(Event)]
+pub struct RespawnAtCheckpoint;
+
+#[derive(Resource)]
+pub struct ActiveCheckpoint {
+    pub entity: Entity,
+    player: Transform,
+    wiring: Option<WiringGraph>,
+}
+
+#[derive(Resource)]
+struct Snapshots<T: Checkpointed>(HashMap<Entity, T::Snapshot>);
+
+impl<T: Checkpointed> Default for Snapshots<T> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CheckpointSet {
+    Reach,
+    Snapshot,
+    Restore,
+}
+
+pub trait CheckpointAppExt {
+    fn add_checkpointed<T: Checkpointed>(&mut self) -> &mut Self;
+}
+
+impl CheckpointAppExt for App {
+    fn add_checkpointed<T: Checkpointed>(&mut self) -> &mut Self {
+        self.init_resource::<Snapshots<T>>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    snapshot::<T>.in_set(CheckpointSet::Snapshot),
+                    restore::<T>.in_set(CheckpointSet::Restore),
+                ),
+            )
+            .add_systems(Update, clear::<T>.run_if(state_changed::<GameState>()))
+    }
+}
+
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CheckpointReached>()
+            .add_event::<RespawnAtCheckpoint>()
+            .configure_sets(
+                FixedUpdate,
+                (
+                    CheckpointSet::Reach,
+                    CheckpointSet::Snapshot,
+                    CheckpointSet::Restore,
+                )
+                    .chain()
+                    .in_set(SimulationSet::Logic)
+                    .after(WiringSet::Apply)
+                    .run_if(resource_exists::<Player>())
+                    .run_if(not(any_with_component::<Loading>())),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    reach.in_set(CheckpointSet::Reach),
+                    respawn.in_set(CheckpointSet::Restore),
+                ),
+            )
+            .add_systems(Update, cleanup.run_if(state_changed::<GameState>()));
+    }
+}
+
+fn reach(
+    mut commands: Commands,
+    mut reached: EventWriter<CheckpointReached>,
+    checkpoints: Query<(Entity, &SensorContacts), (With<Checkpoint>, Changed<SensorContacts>)>,
+    player: Query<(Entity, &Transform), With<PlayerPhysics>>,
+    active: Option<Res<ActiveCheckpoint>>,
+    graph: Option<Res<WiringGraph>>,
+    state: Res<State<GameState>>,
+) {
+    let Ok((player, transform)) = player.get_single() else {
+        return;
+    };
+    for (checkpoint, contacts) in checkpoints.iter() {
+        if !contacts.contains(player) {
+            continue;
+        }
+        if active.as_ref().map_or(false, |a| a.entity == checkpoint) {
+            continue;
+        }
+
+        commands.insert_resource(ActiveCheckpoint {
+            entity: checkpoint,
+            player: *transform,
+            wiring: graph.as_deref().cloned(),
+        });
+        reached.send(CheckpointReached {
+            entity: checkpoint,
+            level: state.get().clone(),
+        });
+    }
+}
+
+fn snapshot<T: Checkpointed>(
+    mut reached: EventReader<CheckpointReached>,
+    mut snapshots: ResMut<Snapshots<T>>,
+    components: Query<(Entity, &T)>,
+) {
+    if reached.read().last().is_none() {
+        return;
+    }
+    snapshots.0 = components
+        .iter()
+        .map(|(entity, component)| (entity, component.snapshot()))
+        .collect();
+}
+
+fn restore<T: Checkpointed>(
+    mut respawned: EventReader<RespawnAtCheckpoint>,
+    mut components: Query<(Entity, &mut T)>,
+    snapshots: Res<Snapshots<T>>,
+) {
+    if respawned.read().last().is_none() {
+        return;
+    }
+    for (entity, mut component) in components.iter_mut() {
+        if let Some(snapshot) = snapshots.0.get(&entity) {
+            component.restore(snapshot);
+        }
+    }
+}
+
+fn respawn(
+    mut respawned: EventReader<RespawnAtCheckpoint>,
+    mut player: ResMut<Player>,
+    mut physics: Query<(&mut Transform, &mut Velocity, &mut Interpolated), With<PlayerPhysics>>,
+    mut graph: Option<ResMut<WiringGraph>>,
+    checkpoint: Option<Res<ActiveCheckpoint>>,
+) {
+    if respawned.read().last().is_none() {
+        return;
+    }
+    let Some(checkpoint) = checkpoint else {
+        return;
+    };
+
+    player.view_controller = None;
+    player.socket = None;
+    if let Ok((mut transform, mut velocity, mut interpolated)) = physics.get_single_mut() {
+        *transform = checkpoint.player;
+        *velocity = Velocity::zero();
+        *interpolated = Interpolated::new(checkpoint.player);
+    }
+    if let (Some(graph), Some(wiring)) = (graph.as_mut(), checkpoint.wiring.as_ref()) {
+        **graph = wiring.clone();
+    }
+}
+
+fn clear<T: Checkpointed>(mut snapshots: ResMut<Snapshots<T>>) {
+    snapshots.0.clear();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ActiveCheckpoint>();
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use bevy::{log::Level, prelude::*, window::WindowMode};
+use clap::Parser;
+use rand::{rngs::StdRng, SeedableRng};
+
+#[derive(Parser, Resource, Clone, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Start directly in the given level instead of the main menu.
+    #[arg(long)]
+    pub level: Option<usize>,
+    /// Run in a window, overriding the saved setting.
+    #[arg(long, conflicts_with = "fullscreen")]
+    pub windowed: bool,
+    /// Run fullscreen, overriding the saved setting.
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Window size, e.g. `1280x720`.
+    #[arg(long, value_parser = parse_resolution)]
+    pub resolution: Option<(f32, f32)>,
+    #[arg(long, default_value = "error")]
+    pub log_level: Level,
+    /// Show the world inspector.
+    #[arg(long)]
+    pub inspector: bool,
+    /// Render physics colliders.
+    #[arg(long)]
+    pub physics_debug: bool,
+    /// Seed for everything random in the game.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Simulate the level without a window or GPU.
+    #[arg(long, conflicts_with = "inspector")]
+    pub headless: bool,
+    /// Exit after this many frames.
+    #[arg(long)]
+    pub frames: Option<u32>,
+    /// Record gameplay input of the level to a file.
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "level",
+        conflicts_with = "replay"
+    )]
+    pub record: Option<PathBuf>,
+    /// Play a level with input recorded by `--record`.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["level", "seed"])]
+    pub replay: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Window mode requested on the command line, if any.
+    pub fn window_mode(&self) -> Option<WindowMode> {
+        if self.fullscreen {
+            Some(WindowMode::BorderlessFullscreen)
+        } else if self.windowed {
+            Some(WindowMode::Windowed)
+        } else {
+            None
+        }
+    }
+
+    pub fn configure_window(&self, window: &mut Window) {
+        if let Some(mode) = self.window_mode() {
+            window.mode = mode;
+        }
+        if let Some((width, height)) = self.resolution {
+            window.resolution.set(width, height);
+        }
+    }
+
+    pub fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+}
+
+fn parse_resolution(value: &str) -> Result<(f32, f32)> {
+    let (width, height) = value
+        .split_once('x')
+        .context("Expected resolution as <width>x<height>")?;
+    Ok((width.trim().parse()?, height.trim().parse()?))
+}
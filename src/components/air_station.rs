@@ -0,0 +1,63 @@
+use std::collections::LinkedList;
+
+use bevy::prelude::*;
+use bevy_rapier2d::geometry::Collider;
+use serde::de::IgnoredAny;
+
+use crate::{game_scene::SceneComponentAppExt, validation::SceneWiringError, GameState};
+
+use super::loading::Loading;
+
+/// Refills the diver's oxygen while the player is inside its "sensor" collider.
+#[derive(Component)]
+pub struct AirStation;
+
+#[derive(Component)]
+pub struct AirStationSensor;
+
+pub struct AirStationPlugin;
+
+impl Plugin for AirStationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_scene_component("air_station", |entity, _: IgnoredAny, _| {
+            entity.insert(AirStation);
+            Ok(())
+        })
+        .add_systems(Update, init.run_if(any_with_component::<Loading>()));
+    }
+}
+
+fn init(
+    mut commands: Commands,
+    mut errors: EventWriter<SceneWiringError>,
+    stations: Query<(Entity, &Name), (With<Loading>, With<AirStation>)>,
+    parents: Query<&Children>,
+    names: Query<&Name>,
+    colliders: Query<&Collider>,
+    state: Res<State<GameState>>,
+) {
+    for (entity, station_name) in stations.iter() {
+        commands.entity(entity).remove::<Loading>();
+
+        let mut found = false;
+        let mut stack = LinkedList::from([entity]);
+        while let Some(current) = stack.pop_back() {
+            if let Ok(name) = names.get(current).map(Name::as_str) {
+                if name.contains("sensor") && colliders.get(current).is_ok() {
+                    commands.entity(current).insert(AirStationSensor);
+                    found = true;
+                }
+            }
+            if let Ok(children) = parents.get(current) {
+                stack.extend(children.into_iter());
+            }
+        }
+
+        if !found {
+            errors.send(
+                SceneWiringError::new(state.get(), station_name, "a \"sensor\" collider")
+                    .disable(entity),
+            );
+        }
+    }
+}
@@ -6,14 +6,25 @@ use bevy_mod_raycast::{
     CursorRay,
 };
 use bevy_rapier2d::geometry::Collider;
+use serde::Deserialize;
 
 use crate::{
-    player::{Player, PlayerCollision, ViewController},
+    checkpoint::{CheckpointAppExt, Checkpointed},
+    game_scene::SceneComponentAppExt,
+    interaction::{Interactable, Interacted},
+    player::{Player, ViewController},
+    simulation::SimulationSet,
     utils::reduce_to_root,
+    validation::{Disabled, SceneWiringError},
+    wiring::{Wired, WiringAppExt},
+    GameState,
 };
 
 use super::loading::Loading;
 
+/// Largest secret that fits the four digits of the screen.
+const MAX_SECRET: u32 = 9999;
+
 #[derive(PartialEq)]
 enum State {
     Idle,
@@ -37,28 +48,38 @@ struct CodeEntities {
     buttons: [CodeButton; 10],
 }
 
+#[derive(Event)]
+pub struct CodeEntered {
+    pub entity: Entity,
+    pub level: GameState,
+    pub correct: bool,
+}
+
 #[derive(Component)]
 pub struct Code {
     entities: Option<CodeEntities>,
     secret: u32,
     input: String,
-    is_action_last: bool,
-    is_mouse_last: bool,
+    /// Button pressed by a click, picked with the mouse or by moving between buttons.
+    focus: Option<u8>,
+    hovered_last: Option<u8>,
+    step_last: IVec2,
     finish_timer: f32,
     state: State,
 }
 
 impl Code {
     pub fn new(secret: u32) -> Self {
-        if secret > 9999 {
+        if secret > MAX_SECRET {
             panic!("Secret can only contain 4 digits");
         }
         Self {
             entities: None,
             secret,
             input: String::default(),
-            is_action_last: false,
-            is_mouse_last: false,
+            focus: None,
+            hovered_last: None,
+            step_last: IVec2::ZERO,
             finish_timer: 0.0,
             state: State::Idle,
         }
@@ -70,34 +91,137 @@ impl Code {
             _ => false,
         }
     }
+
+    /// Digits entered so far.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Presses a keypad button while the code is being entered.
+    pub fn press_button(&mut self, number: u8) {
+        if self.state == State::Acting && self.input.len() < 4 {
+            self.input.push((number + 0x30) as char);
+        }
+    }
+}
+
+impl Wired for Code {
+    const OUTPUTS: &'static [&'static str] = &["activated"];
+    const INPUTS: &'static [&'static str] = &[];
+
+    fn output(&self, _port: &str) -> bool {
+        self.activated()
+    }
+
+    fn input(&mut self, _port: &str, _value: bool) {}
+}
+
+impl Checkpointed for Code {
+    type Snapshot = bool;
+
+    fn snapshot(&self) -> bool {
+        self.activated()
+    }
+
+    fn restore(&mut self, solved: &bool) {
+        self.input.clear();
+        self.focus = None;
+        self.finish_timer = 0.0;
+        self.state = if *solved {
+            State::Success(true)
+        } else {
+            State::Idle
+        };
+    }
+}
+
+#[derive(Deserialize)]
+struct CodeExtras {
+    secret: u32,
 }
 
 pub struct CodePlugin;
 
 impl Plugin for CodePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                init.run_if(any_with_component::<Loading>()),
+        app.add_event::<CodeEntered>()
+            .add_wired::<Code>()
+            .add_checkpointed::<Code>()
+            .add_scene_component("code", |entity, extras: CodeExtras, _| {
+                if extras.secret > MAX_SECRET {
+                    return Err("a secret of at most 4 digits".to_string());
+                }
+                entity.insert(Code::new(extras.secret));
+                Ok(())
+            })
+            .add_systems(Update, init.run_if(any_with_component::<Loading>()))
+            .add_systems(
+                FixedUpdate,
                 update
+                    .in_set(SimulationSet::Interact)
                     .run_if(any_with_component::<Code>())
                     .run_if(not(any_with_component::<Loading>())),
-            ),
-        );
+            );
+    }
+}
+
+/// Zero-based digit and segment of a display node named like `segment_2_5`.
+pub(crate) fn segment_index(name: &str) -> Option<(usize, usize)> {
+    let from = name.find("segment_")? + 8;
+    match (name.as_bytes().get(from), name.as_bytes().get(from + 2)) {
+        (Some(digit @ b'1'..=b'4'), Some(segment @ b'1'..=b'7')) => {
+            Some(((digit - b'1') as usize, (segment - b'1') as usize))
+        }
+        _ => None,
+    }
+}
+
+/// Number of a keypad node named like `btn_7`.
+pub(crate) fn button_number(name: &str) -> Option<u8> {
+    let from = name.find("btn_")? + 4;
+    match name.as_bytes().get(from) {
+        Some(number @ b'0'..=b'9') => Some(number - b'0'),
+        _ => None,
+    }
+}
+
+/// Parts of a code panel that were not found in its scene.
+pub(crate) fn missing_parts(
+    screen: bool,
+    sensor: bool,
+    segments: &[[bool; 7]; 4],
+    buttons: &[bool; 10],
+) -> Vec<String> {
+    let mut missing = vec![];
+    if !screen {
+        missing.push("a \"screen\" mesh".to_string());
+    }
+    if !sensor {
+        missing.push("a \"sensor\" collider".to_string());
     }
+    for (digit, segments) in segments.iter().enumerate() {
+        for (segment, _) in segments.iter().enumerate().filter(|(_, found)| !**found) {
+            missing.push(format!("\"segment_{}_{}\"", digit + 1, segment + 1));
+        }
+    }
+    for (number, _) in buttons.iter().enumerate().filter(|(_, found)| !**found) {
+        missing.push(format!("\"btn_{number}\""));
+    }
+    missing
 }
 
 fn init(
     mut commands: Commands,
-    mut codes: Query<(Entity, &mut Code), With<Loading>>,
+    mut codes: Query<(Entity, &mut Code, &Name), With<Loading>>,
     mut visibility: Query<&mut Visibility>,
+    mut errors: EventWriter<SceneWiringError>,
     parents: Query<&Children>,
     names: Query<&Name>,
     colliders: Query<&Collider>,
     mesh_hs: Query<&Handle<Mesh>>,
+    state: Res<bevy::prelude::State<GameState>>,
 ) {
-    for (entity, mut code) in codes.iter_mut() {
+    for (entity, mut code, code_name) in codes.iter_mut() {
         commands.entity(entity).remove::<Loading>();
 
         let mut sensor = None;
@@ -113,15 +237,17 @@ fn init(
                 } else if name.contains("sensor") && colliders.get(current).is_ok() {
                     sensor = Some(current);
                 } else if name.contains("segment_") {
-                    let from = name.find("segment_").unwrap() + 8;
-                    let digit = name.as_bytes()[from] - 0x30 - 1;
-                    let segment = name.as_bytes()[from + 2] - 0x30 - 1;
-                    segments[digit as usize][segment as usize] = Some(current);
-                    *visibility.get_mut(current).unwrap() = Visibility::Hidden;
+                    // Malformed names are reported below as missing segments.
+                    if let Some((digit, segment)) = segment_index(name) {
+                        segments[digit][segment] = Some(current);
+                        if let Ok(mut visibility) = visibility.get_mut(current) {
+                            *visibility = Visibility::Hidden;
+                        }
+                    }
                 } else if name.contains("btn_") {
-                    let from = name.find("btn_").unwrap() + 4;
-                    let number = name.as_bytes()[from] - 0x30;
-                    buttons[number as usize] = Some((current, number));
+                    if let Some(number) = button_number(name) {
+                        buttons[number as usize] = Some((current, number));
+                    }
                 }
             }
             if let Ok(children) = parents.get(current) {
@@ -129,71 +255,83 @@ fn init(
             }
         }
 
-        code.entities = Some(CodeEntities {
-            screen: screen.unwrap(),
-            sensor: sensor.unwrap(),
-            segments: segments
-                .iter()
-                .map(|digit| {
-                    digit
-                        .iter()
-                        .map(|segment| segment.unwrap())
-                        .collect::<Vec<_>>()
-                        .try_into()
-                        .unwrap()
-                })
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap(),
-            buttons: buttons
-                .iter()
-                .map(|btn| {
-                    let (entity, number) = btn.unwrap();
-                    CodeButton {
-                        entity,
-                        number,
-                        timer: 0.0,
-                    }
-                })
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap(),
-        });
+        let entities = (|| {
+            Some(CodeEntities {
+                screen: screen?,
+                sensor: sensor?,
+                segments: segments
+                    .iter()
+                    .map(|digit| {
+                        digit
+                            .iter()
+                            .copied()
+                            .collect::<Option<Vec<_>>>()?
+                            .try_into()
+                            .ok()
+                    })
+                    .collect::<Option<Vec<_>>>()?
+                    .try_into()
+                    .ok()?,
+                buttons: buttons
+                    .iter()
+                    .map(|btn| {
+                        btn.map(|(entity, number)| CodeButton {
+                            entity,
+                            number,
+                            timer: 0.0,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?
+                    .try_into()
+                    .ok()?,
+            })
+        })();
+
+        let Some(entities) = entities else {
+            let missing = missing_parts(
+                screen.is_some(),
+                sensor.is_some(),
+                &segments.map(|digit| digit.map(|segment| segment.is_some())),
+                &buttons.map(|button| button.is_some()),
+            );
+            errors.send(
+                SceneWiringError::new(state.get(), code_name, missing.join(", ")).disable(entity),
+            );
+            continue;
+        };
+        commands
+            .entity(entities.sensor)
+            .insert(Interactable::new("use the keypad"));
+        code.entities = Some(entities);
     }
 }
 
 fn update(
     mut player: ResMut<Player>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut codes: Query<(&mut Code, &Name)>,
+    mut codes: Query<(Entity, &mut Code, &Name), Without<Disabled>>,
+    mut entered: EventWriter<CodeEntered>,
     mut transforms: Query<&mut Transform>,
     mut raycast: Raycast,
     mut visibility: Query<&mut Visibility>,
+    mut interactables: Query<&mut Interactable>,
+    mut interacted: EventReader<Interacted>,
     cursor_ray: Res<CursorRay>,
     time: Res<Time>,
-    collisions: Query<&PlayerCollision>,
     transforms_g: Query<&GlobalTransform>,
     children: Query<&Parent>,
     material_hs: Query<&Handle<StandardMaterial>>,
+    state: Res<bevy::prelude::State<GameState>>,
 ) {
-    for (mut code, code_name) in codes.iter_mut() {
+    let interacted = interacted.read().map(|i| i.entity).collect::<Vec<_>>();
+    for (code_entity, mut code, code_name) in codes.iter_mut() {
         let entities = code.entities.clone().unwrap();
 
-        let inside = collisions
-            .iter()
-            .find(|c| c.other == entities.sensor)
-            .is_some();
-
-        let acted = !code.is_action_last && player.is_action;
-        code.is_action_last = player.is_action;
-
-        let clicked = !code.is_mouse_last && player.is_mouse;
-        code.is_mouse_last = player.is_mouse;
-
         match code.state {
             State::Idle => {
-                if inside && acted {
+                if interacted.contains(&entities.sensor) {
                     code.state = State::Acting;
+                    code.focus = None;
 
                     let screen = transforms_g.get(entities.screen).unwrap();
                     let from = screen.translation() - 3.0 * screen.forward() - 1.25 * screen.up();
@@ -207,37 +345,69 @@ fn update(
                 }
             }
             State::Acting => {
-                if acted {
+                if player.interact.consume() {
                     code.state = State::Idle;
                     player.view_controller = None;
                 } else {
-                    let mut btn_clicked = None;
-                    if let Some(cursor_ray) = **cursor_ray {
-                        let buttons = code.entities.as_mut().map(|e| &mut e.buttons).unwrap();
+                    let hovered = (**cursor_ray).and_then(|cursor_ray| {
                         let [(entity, _)] =
                             raycast.cast_ray(cursor_ray, &RaycastSettings::default())
                         else {
-                            return;
+                            return None;
                         };
-                        for btn in buttons {
-                            if reduce_to_root(&children, *entity, false, |f, p| {
-                                f || (p == btn.entity)
-                            }) {
-                                btn.timer += time.delta_seconds() * 10.0;
-                                btn_clicked = Some(btn.number);
-                            } else {
-                                btn.timer -= time.delta_seconds() * 10.0;
+                        entities
+                            .buttons
+                            .iter()
+                            .find(|btn| {
+                                reduce_to_root(&children, *entity, false, |f, p| {
+                                    f || (p == btn.entity)
+                                })
+                            })
+                            .map(|btn| btn.number)
+                    });
+                    if hovered != code.hovered_last {
+                        code.focus = hovered;
+                        code.hovered_last = hovered;
+                    }
+
+                    let step = step(player.move_vec);
+                    if step != IVec2::ZERO && step != code.step_last {
+                        let screen = transforms_g.get(entities.screen).unwrap();
+                        let (right, up) = (screen.forward().cross(screen.up()), screen.up());
+                        let positions = entities
+                            .buttons
+                            .iter()
+                            .map(|btn| {
+                                let p = transforms_g.get(btn.entity).unwrap().translation();
+                                (btn.number, Vec2::new(p.dot(right), p.dot(up)))
+                            })
+                            .collect::<Vec<_>>();
+                        code.focus = match code.focus {
+                            Some(from) => {
+                                neighbour(&positions, from, step.as_vec2()).or(Some(from))
                             }
-                            let mut transform = transforms.get_mut(btn.entity).unwrap();
-                            btn.timer = btn.timer.max(0.0).min(1.0);
-                            let base = 2.0867615;
-                            let amount = if clicked { 0.2 } else { 0.1 };
-                            transform.translation.z = base - btn.timer * amount;
+                            None => central(&positions),
+                        };
+                    }
+                    code.step_last = step;
+
+                    let focus = code.focus;
+                    let buttons = code.entities.as_mut().map(|e| &mut e.buttons).unwrap();
+                    for btn in buttons {
+                        if focus == Some(btn.number) {
+                            btn.timer += time.delta_seconds() * 10.0;
+                        } else {
+                            btn.timer -= time.delta_seconds() * 10.0;
                         }
+                        let mut transform = transforms.get_mut(btn.entity).unwrap();
+                        btn.timer = btn.timer.max(0.0).min(1.0);
+                        let base = 2.0867615;
+                        let amount = if player.click.held() { 0.2 } else { 0.1 };
+                        transform.translation.z = base - btn.timer * amount;
                     }
-                    if let Some(btn_clicked) = btn_clicked {
-                        if clicked {
-                            code.input.push((btn_clicked + 0x30) as char);
+                    if let Some(focus) = focus {
+                        if player.click.consume() {
+                            code.press_button(focus);
                         }
                     }
                     if code.input.len() == 4 {
@@ -252,6 +422,13 @@ fn update(
                 let material = materials
                     .get_mut(material_hs.get(entities.screen).unwrap())
                     .unwrap();
+                if code.finish_timer >= 1.0 {
+                    entered.send(CodeEntered {
+                        entity: code_entity,
+                        level: state.get().clone(),
+                        correct: secret == code.secret,
+                    });
+                }
                 if secret == code.secret {
                     if code.finish_timer >= 1.0 {
                         code.state = State::Success(false);
@@ -274,6 +451,10 @@ fn update(
             State::Success(true) => {}
         }
 
+        if let Ok(mut interactable) = interactables.get_mut(entities.sensor) {
+            interactable.enabled = code.state == State::Idle;
+        }
+
         for (i, segment) in entities.segments.iter().enumerate() {
             let mask = match code.input.as_bytes().get(i) {
                 Some(b'0') => 0b1110111,
@@ -299,3 +480,112 @@ fn update(
         }
     }
 }
+
+/// Direction of a step between keypad buttons for a movement input.
+fn step(move_vec: Vec2) -> IVec2 {
+    if move_vec.length() < 0.5 {
+        IVec2::ZERO
+    } else if move_vec.x.abs() > move_vec.y.abs() {
+        IVec2::new(move_vec.x.signum() as i32, 0)
+    } else {
+        IVec2::new(0, move_vec.y.signum() as i32)
+    }
+}
+
+/// Nearest button roughly in `direction` from the button `from`.
+fn neighbour(positions: &[(u8, Vec2)], from: u8, direction: Vec2) -> Option<u8> {
+    let (_, origin) = positions.iter().find(|(number, _)| *number == from)?;
+    positions
+        .iter()
+        .map(|(number, p)| (*number, *p - *origin))
+        .filter(|(_, offset)| offset.dot(direction) > 0.7 * offset.length())
+        .min_by(|(_, a), (_, b)| a.length_squared().total_cmp(&b.length_squared()))
+        .map(|(number, _)| number)
+}
+
+/// Button closest to the middle of the keypad.
+fn central(positions: &[(u8, Vec2)]) -> Option<u8> {
+    let center = positions.iter().map(|(_, p)| *p).sum::<Vec2>() / positions.len() as f32;
+    positions
+        .iter()
+        .min_by(|(_, a), (_, b)| {
+            a.distance_squared(center)
+                .total_cmp(&b.distance_squared(center))
+        })
+        .map(|(number, _)| *number)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bevy::ecs::system::CommandQueue;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::game_scene::SceneComponents;
+
+    /// Phone keypad: 1 2 3 / 4 5 6 / 7 8 9 / _ 0 _
+    fn keypad() -> Vec<(u8, Vec2)> {
+        let mut positions = (1..=9)
+            .map(|n| {
+                let i = (n - 1) as f32;
+                (n, Vec2::new(i % 3.0, -(i / 3.0).floor()))
+            })
+            .collect::<Vec<_>>();
+        positions.push((0, Vec2::new(1.0, -3.0)));
+        positions
+    }
+
+    #[test]
+    fn neighbours() {
+        let keypad = keypad();
+        assert_eq!(neighbour(&keypad, 5, Vec2::Y), Some(2));
+        assert_eq!(neighbour(&keypad, 5, Vec2::NEG_Y), Some(8));
+        assert_eq!(neighbour(&keypad, 5, Vec2::NEG_X), Some(4));
+        assert_eq!(neighbour(&keypad, 5, Vec2::X), Some(6));
+        assert_eq!(neighbour(&keypad, 8, Vec2::NEG_Y), Some(0));
+    }
+
+    #[test]
+    fn no_neighbour_past_the_edge() {
+        let keypad = keypad();
+        assert_eq!(neighbour(&keypad, 3, Vec2::X), None);
+        assert_eq!(neighbour(&keypad, 2, Vec2::Y), None);
+        assert_eq!(neighbour(&keypad, 0, Vec2::NEG_Y), None);
+        assert_eq!(neighbour(&keypad, 42, Vec2::X), None);
+    }
+
+    #[test]
+    fn secret_from_extras() {
+        let mut app = App::new();
+        app.add_plugins(CodePlugin);
+        let world = &app.world;
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, world);
+        let mut insert = |secret| {
+            let Value::Object(extras) = json!({ "secret": secret }) else {
+                unreachable!();
+            };
+            world.resource::<SceneComponents>().insert(
+                &mut commands.spawn_empty(),
+                "code",
+                extras,
+                &HashMap::new(),
+            )
+        };
+
+        assert_eq!(insert(152), Ok(()));
+        assert_eq!(insert(9999), Ok(()));
+        assert_eq!(
+            insert(10000),
+            Err("a secret of at most 4 digits".to_string())
+        );
+    }
+
+    #[test]
+    fn central_button() {
+        assert_eq!(central(&keypad()), Some(5));
+        assert_eq!(central(&[]), None);
+    }
+}
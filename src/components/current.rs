@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, LinkedList},
+    f32::consts::TAU,
+};
+
+use bevy::prelude::*;
+use bevy_rapier2d::{
+    dynamics::{ExternalImpulse, RigidBody},
+    geometry::Collider,
+};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    game_scene::SceneComponentAppExt,
+    player::{process_movement, Player, PlayerPhysics},
+    sensor::Sensors,
+    simulation::SimulationSet,
+    utils::GameRng,
+    validation::{Disabled, SceneWiringError},
+    GameState,
+};
+
+use super::loading::Loading;
+
+/// How fast the turbulence of a current sways, in radians per second.
+const TURBULENCE_SPEED: f32 = 3.0;
+
+/// Water flowing through a collider volume. It pushes the player and every dynamic body
+/// inside it; the pushes of overlapping volumes add up.
+#[derive(Component, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CurrentVolume {
+    /// Direction of the flow in the local space of the volume.
+    pub direction: Vec2,
+    pub strength: f32,
+    /// Share of the strength lost per unit of distance downstream of the volume's origin.
+    pub falloff: f32,
+    /// Sideways sway, as a share of the strength.
+    pub turbulence: f32,
+    pub active: bool,
+    #[serde(skip)]
+    sensor: Option<Entity>,
+    /// Start of the turbulence sway, drawn from the game's seeded randomness.
+    #[serde(skip)]
+    phase: f32,
+}
+
+impl Default for CurrentVolume {
+    fn default() -> Self {
+        Self {
+            direction: Vec2::Y,
+            strength: 15.0,
+            falloff: 0.0,
+            turbulence: 0.0,
+            active: true,
+            sensor: None,
+            phase: 0.0,
+        }
+    }
+}
+
+impl CurrentVolume {
+    /// A current whose volume is the `sensor` collider instead of a collider below it.
+    pub fn new(sensor: Entity, direction: Vec2, strength: f32) -> Self {
+        Self {
+            direction,
+            strength,
+            sensor: Some(sensor),
+            ..Default::default()
+        }
+    }
+
+    /// Push at world `position`, `elapsed` seconds into the level.
+    fn force(&self, transform: &GlobalTransform, position: Vec2, elapsed: f32) -> Vec2 {
+        let flow = transform
+            .affine()
+            .transform_vector3(self.direction.extend(0.0))
+            .xy()
+            .normalize_or_zero();
+        let downstream = (position - transform.translation().xy()).dot(flow).max(0.0);
+        let strength = self.strength * (1.0 - self.falloff * downstream).clamp(0.0, 1.0);
+        let sway = (TURBULENCE_SPEED * elapsed + self.phase + position.dot(flow)).sin();
+        (flow + flow.perp() * self.turbulence * sway) * strength
+    }
+}
+
+pub struct CurrentPlugin;
+
+impl Plugin for CurrentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_scene_component("current", |entity, volume: CurrentVolume, _| {
+            entity.insert(volume);
+            Ok(())
+        })
+        .add_systems(Update, init.run_if(any_with_component::<Loading>()))
+        .add_systems(Update, randomize.run_if(resource_exists::<GameRng>()))
+        .add_systems(
+            FixedUpdate,
+            push.in_set(SimulationSet::Control)
+                .before(process_movement)
+                .run_if(resource_exists::<Player>())
+                .run_if(not(any_with_component::<Loading>())),
+        );
+    }
+}
+
+fn init(
+    mut commands: Commands,
+    mut volumes: Query<(Entity, &mut CurrentVolume, &Name), With<Loading>>,
+    mut errors: EventWriter<SceneWiringError>,
+    parents: Query<&Children>,
+    colliders: Query<&Collider>,
+    state: Res<State<GameState>>,
+) {
+    for (entity, mut volume, volume_name) in volumes.iter_mut() {
+        commands.entity(entity).remove::<Loading>();
+
+        let mut stack = LinkedList::from([entity]);
+        while let Some(current) = stack.pop_back() {
+            if colliders.get(current).is_ok() {
+                volume.sensor = Some(current);
+                break;
+            }
+            if let Ok(children) = parents.get(current) {
+                stack.extend(children.into_iter());
+            }
+        }
+
+        if volume.sensor.is_none() {
+            errors.send(
+                SceneWiringError::new(state.get(), volume_name, "a collider").disable(entity),
+            );
+        }
+    }
+}
+
+fn randomize(
+    mut volumes: Query<&mut CurrentVolume, Added<CurrentVolume>>,
+    mut rng: ResMut<GameRng>,
+) {
+    for mut volume in volumes.iter_mut() {
+        volume.phase = rng.0.gen_range(0.0..TAU);
+    }
+}
+
+fn push(
+    mut commands: Commands,
+    mut player: ResMut<Player>,
+    mut impulses: Query<&mut ExternalImpulse, Without<PlayerPhysics>>,
+    volumes: Query<(&CurrentVolume, &GlobalTransform), Without<Disabled>>,
+    bodies: Query<(&RigidBody, &GlobalTransform)>,
+    player_physics: Query<Entity, With<PlayerPhysics>>,
+    sensors: Sensors,
+    time: Res<Time>,
+) {
+    let mut forces = HashMap::<Entity, Vec2>::new();
+    for (volume, transform) in volumes.iter() {
+        let Some(sensor) = volume.sensor.filter(|_| volume.active) else {
+            continue;
+        };
+        let Some(contacts) = sensors.contacts(sensor) else {
+            continue;
+        };
+        for body in contacts.bodies() {
+            let Ok((RigidBody::Dynamic, body_transform)) = bodies.get(body) else {
+                continue;
+            };
+            let position = body_transform.translation().xy();
+            *forces.entry(body).or_default() +=
+                volume.force(transform, position, time.elapsed_seconds());
+        }
+    }
+
+    // The player turns its push into a velocity of its own, see `process_movement`.
+    player.push_vec = player_physics
+        .get_single()
+        .ok()
+        .and_then(|entity| forces.remove(&entity))
+        .unwrap_or_default();
+
+    for (body, force) in forces {
+        let impulse = force * time.delta_seconds();
+        match impulses.get_mut(body) {
+            Ok(mut external) => external.impulse += impulse,
+            Err(_) => {
+                commands.entity(body).insert(ExternalImpulse {
+                    impulse,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume() -> CurrentVolume {
+        CurrentVolume {
+            direction: Vec2::X,
+            strength: 10.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn follows_the_volume_rotation() {
+        let transform = GlobalTransform::from(Transform::from_rotation(Quat::from_rotation_z(
+            std::f32::consts::FRAC_PI_2,
+        )));
+        let force = volume().force(&transform, Vec2::ZERO, 0.0);
+        assert!(force.abs_diff_eq(Vec2::new(0.0, 10.0), 1e-4), "{force}");
+    }
+
+    #[test]
+    fn falloff() {
+        let current = CurrentVolume {
+            falloff: 0.1,
+            ..volume()
+        };
+        let transform = GlobalTransform::from_xyz(1.0, 0.0, 0.0);
+        let force = |x| current.force(&transform, Vec2::new(x, 0.0), 0.0).x;
+        // Nothing is lost upstream of the origin.
+        assert_eq!(force(-4.0), 10.0);
+        assert_eq!(force(1.0), 10.0);
+        assert!((force(6.0) - 5.0).abs() < 1e-4);
+        assert_eq!(force(20.0), 0.0);
+    }
+
+    #[test]
+    fn turbulence_sways_sideways() {
+        let current = CurrentVolume {
+            turbulence: 0.5,
+            ..volume()
+        };
+        let transform = GlobalTransform::IDENTITY;
+        let forces = (0..20)
+            .map(|i| current.force(&transform, Vec2::ZERO, i as f32 * 0.1))
+            .collect::<Vec<_>>();
+        assert!(forces.iter().all(|force| force.x == 10.0));
+        assert!(forces.iter().all(|force| force.y.abs() <= 5.0 + 1e-4));
+        assert!(forces.iter().any(|force| force.y > 1.0));
+        assert!(forces.iter().any(|force| force.y < -1.0));
+
+        let calm = volume().force(&transform, Vec2::ZERO, 1.0);
+        assert_eq!(calm, Vec2::new(10.0, 0.0));
+    }
+
+    #[test]
+    fn zero_direction_has_no_force() {
+        let current = CurrentVolume {
+            direction: Vec2::ZERO,
+            ..volume()
+        };
+        assert_eq!(
+            current.force(&GlobalTransform::IDENTITY, Vec2::ZERO, 0.0),
+            Vec2::ZERO
+        );
+    }
+}
@@ -6,14 +6,28 @@ use std::{
 
 use bevy::prelude::*;
 use bevy_rapier2d::geometry::{Collider, Sensor};
+use serde::de::IgnoredAny;
 
-use crate::player::{Player, PlayerCollision};
+use crate::{
+    game_scene::SceneComponentAppExt,
+    simulation::SimulationSet,
+    validation::{Disabled, SceneWiringError},
+    wiring::{Wired, WiringAppExt},
+    GameState,
+};
+
+use super::{current::CurrentVolume, loading::Loading};
 
-use super::loading::Loading;
+#[derive(Event)]
+pub struct FanStopped {
+    pub entity: Entity,
+    pub level: GameState,
+}
 
 #[derive(Component)]
 pub struct Fan {
     pub spinning: bool,
+    was_spinning: bool,
     factor: f32,
     pusher: Option<Entity>,
     model: Option<Entity>,
@@ -23,6 +37,7 @@ impl Fan {
     pub fn new() -> Self {
         Self {
             spinning: true,
+            was_spinning: true,
             factor: 1.0,
             pusher: None,
             model: None,
@@ -30,30 +45,50 @@ impl Fan {
     }
 }
 
+impl Wired for Fan {
+    const OUTPUTS: &'static [&'static str] = &["spinning"];
+    const INPUTS: &'static [&'static str] = &["power"];
+
+    fn output(&self, _port: &str) -> bool {
+        self.spinning
+    }
+
+    fn input(&mut self, _port: &str, value: bool) {
+        self.spinning = value;
+    }
+}
+
 pub struct FanPlugin;
 
 impl Plugin for FanPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                init.run_if(any_with_component::<Loading>()),
+        app.add_event::<FanStopped>()
+            .add_wired::<Fan>()
+            .add_scene_component("fan", |entity, _: IgnoredAny, _| {
+                entity.insert(Fan::new());
+                Ok(())
+            })
+            .add_systems(Update, init.run_if(any_with_component::<Loading>()))
+            .add_systems(
+                FixedUpdate,
                 update
+                    .in_set(SimulationSet::Interact)
                     .run_if(any_with_component::<Fan>())
                     .run_if(not(any_with_component::<Loading>())),
-            ),
-        );
+            );
     }
 }
 
 fn init(
     mut commands: Commands,
-    mut fans: Query<(Entity, &mut Fan), With<Loading>>,
+    mut fans: Query<(Entity, &mut Fan, &Name), With<Loading>>,
+    mut errors: EventWriter<SceneWiringError>,
     parents: Query<&Children>,
     names: Query<&Name>,
     colliders: Query<&Collider>,
+    state: Res<State<GameState>>,
 ) {
-    for (entity, mut fan) in fans.iter_mut() {
+    for (entity, mut fan, fan_name) in fans.iter_mut() {
         commands.entity(entity).remove::<Loading>();
 
         let mut pusher = None;
@@ -74,33 +109,60 @@ fn init(
             }
         }
 
+        let mut missing = vec![];
+        if pusher.is_none() {
+            missing.push("a \"pusher\" collider");
+        }
+        if model.is_none() {
+            missing.push("a \"fan.\" model");
+        }
+        if !missing.is_empty() {
+            errors.send(
+                SceneWiringError::new(state.get(), fan_name, missing.join(", ")).disable(entity),
+            );
+            continue;
+        }
+
         fan.pusher = pusher;
         fan.model = model;
+        commands
+            .entity(entity)
+            .insert(CurrentVolume::new(pusher.unwrap(), Vec2::Y, 15.0));
     }
 }
 
 fn update(
     mut commands: Commands,
-    mut player: ResMut<Player>,
-    mut fans: Query<(&mut Fan, &GlobalTransform)>,
+    mut fans: Query<(Entity, &mut Fan, &mut CurrentVolume, &Name), Without<Disabled>>,
+    mut stopped: EventWriter<FanStopped>,
+    mut errors: EventWriter<SceneWiringError>,
     mut transforms: Query<&mut Transform>,
     time: Res<Time>,
-    collisions: Query<&PlayerCollision>,
+    state: Res<State<GameState>>,
 ) {
-    player.push_vec = Vec2::ZERO;
-    for (mut fan, transform_g) in fans.iter_mut() {
-        transforms
-            .get_mut(fan.model.unwrap())
-            .unwrap()
-            .rotate_y(fan.factor * 10.0 * PI * time.delta_seconds());
-        if fan.spinning {
-            let pusher = fan.pusher.unwrap();
-            if collisions.iter().find(|c| c.other == pusher).is_some() {
-                player.push_vec += transform_g.up().xy().normalize_or_zero() * 15.0;
-            }
-        } else {
-            fan.factor -= time.delta_seconds();
-            fan.factor = fan.factor.max(0.0).min(1.0);
+    for (entity, mut fan, mut current, fan_name) in fans.iter_mut() {
+        if fan.was_spinning && !fan.spinning {
+            stopped.send(FanStopped {
+                entity,
+                level: state.get().clone(),
+            });
         }
+        fan.was_spinning = fan.spinning;
+
+        let Some(mut model) = fan.model.and_then(|model| transforms.get_mut(model).ok()) else {
+            // Disabled right away, so that later steps of this frame do not report it again.
+            commands.entity(entity).insert(Disabled);
+            errors.send(
+                SceneWiringError::new(state.get(), fan_name, "a \"fan.\" model").disable(entity),
+            );
+            continue;
+        };
+        model.rotate_y(fan.factor * 10.0 * PI * time.delta_seconds());
+        current.active = fan.spinning;
+
+        // Spins down when powered off and back up when powered on again.
+        let target = if fan.spinning { 1.0 } else { 0.0 };
+        let delta = time.delta_seconds();
+        fan.factor += (target - fan.factor).clamp(-delta, delta);
     }
 }
@@ -1,16 +1,29 @@
-use std::{
-    collections::{HashMap, LinkedList},
-    time::Duration,
-};
+use std::{collections::LinkedList, time::Duration};
 
 use bevy::prelude::*;
 use bevy_rapier2d::geometry::{Collider, Sensor};
+use serde::de::IgnoredAny;
+
+use crate::{
+    checkpoint::{CheckpointAppExt, Checkpointed},
+    game_scene::SceneComponentAppExt,
+    simulation::SimulationSet,
+    validation::{Disabled, SceneWiringError},
+    wiring::{Wired, WiringAppExt},
+    GameState,
+};
 
 use super::loading::Loading;
 
 #[derive(Component)]
 struct GatePhysics(String);
 
+#[derive(Event)]
+pub struct GateOpened {
+    pub entity: Entity,
+    pub level: GameState,
+}
+
 #[derive(Component)]
 pub struct Gate {
     is_open: bool,
@@ -19,11 +32,11 @@ pub struct Gate {
 }
 
 impl Gate {
-    pub fn new(animations: &HashMap<String, Handle<AnimationClip>>) -> Self {
+    pub fn new(animation: Handle<AnimationClip>) -> Self {
         Self {
             is_open: false,
             start_animation: false,
-            animation: animations.get("gate_open").unwrap().clone_weak(),
+            animation,
         }
     }
 
@@ -35,38 +48,89 @@ impl Gate {
         self.is_open = true;
         self.start_animation = true;
     }
+
+    pub fn close(&mut self) {
+        self.is_open = false;
+        self.start_animation = true;
+    }
+}
+
+impl Wired for Gate {
+    const OUTPUTS: &'static [&'static str] = &["opened"];
+    const INPUTS: &'static [&'static str] = &["open"];
+
+    fn output(&self, _port: &str) -> bool {
+        self.opened()
+    }
+
+    fn input(&mut self, _port: &str, value: bool) {
+        if value && !self.is_open {
+            self.open();
+        } else if !value && self.is_open {
+            self.close();
+        }
+    }
+}
+
+impl Checkpointed for Gate {
+    type Snapshot = bool;
+
+    fn snapshot(&self) -> bool {
+        self.is_open
+    }
+
+    fn restore(&mut self, is_open: &bool) {
+        if *is_open && !self.is_open {
+            self.open();
+        } else if !*is_open && self.is_open {
+            self.close();
+        }
+    }
 }
 
 pub struct GatePlugin;
 
 impl Plugin for GatePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                init.run_if(any_with_component::<Loading>()),
+        app.add_event::<GateOpened>()
+            .add_wired::<Gate>()
+            .add_checkpointed::<Gate>()
+            .add_scene_component("gate", |entity, _: IgnoredAny, animations| {
+                let animation = animations
+                    .get("gate_open")
+                    .ok_or_else(|| "an animation \"gate_open\"".to_string())?;
+                entity.insert(Gate::new(animation.clone_weak()));
+                Ok(())
+            })
+            .add_systems(Update, init.run_if(any_with_component::<Loading>()))
+            .add_systems(
+                FixedUpdate,
                 update
+                    .in_set(SimulationSet::Interact)
                     .run_if(any_with_component::<GatePhysics>())
                     .run_if(not(any_with_component::<Loading>())),
-            ),
-        );
+            );
     }
 }
 
 fn init(
     mut commands: Commands,
+    mut errors: EventWriter<SceneWiringError>,
     gates: Query<(Entity, &Name), (With<Loading>, With<Gate>)>,
     parents: Query<&Children>,
     names: Query<&Name>,
     colliders: Query<&Collider>,
+    state: Res<State<GameState>>,
 ) {
     for (entity, gate_name) in gates.iter() {
         commands.entity(entity).remove::<Loading>();
 
+        let mut physics = false;
         let mut stack = LinkedList::from([entity]);
         while let Some(current) = stack.pop_back() {
             if let Ok(name) = names.get(current).map(Name::as_str) {
                 if name.contains("physics") && colliders.get(current).is_ok() {
+                    physics = true;
                     commands
                         .entity(current)
                         .insert(GatePhysics(gate_name.to_string()));
@@ -76,15 +140,24 @@ fn init(
                 stack.extend(children.into_iter());
             }
         }
+
+        if !physics {
+            errors.send(
+                SceneWiringError::new(state.get(), gate_name, "a \"physics\" collider")
+                    .disable(entity),
+            );
+        }
     }
 }
 
 fn update(
     mut commands: Commands,
-    mut gates: Query<(&mut Gate, &mut AnimationPlayer, &Name)>,
+    mut gates: Query<(Entity, &mut Gate, &mut AnimationPlayer, &Name), Without<Disabled>>,
+    mut opened: EventWriter<GateOpened>,
     physics: Query<(Entity, &GatePhysics)>,
+    state: Res<State<GameState>>,
 ) {
-    for (mut gate, mut animation_player, gate_name) in gates.iter_mut() {
+    for (gate_entity, mut gate, mut animation_player, gate_name) in gates.iter_mut() {
         let (entity, _) = physics
             .iter()
             .find(|(_, physics)| physics.0 == gate_name.as_str())
@@ -95,7 +168,12 @@ fn update(
             animation_player
                 .play_with_transition(gate.animation.clone_weak(), Duration::from_millis(250))
                 .set_speed(if gate.is_open { 1.0 } else { -1.0 });
-            if !gate.is_open {
+            if gate.is_open {
+                opened.send(GateOpened {
+                    entity: gate_entity,
+                    level: state.get().clone(),
+                });
+            } else {
                 commands.entity(entity).remove::<Sensor>();
             }
         }
@@ -1,8 +1,45 @@
 pub mod loading;
 
+pub mod air_station;
 pub mod code;
+pub mod current;
 pub mod fan;
 pub mod gate;
+pub mod pressure_plate;
+pub mod prop;
 pub mod security_camera;
 pub mod socket;
 pub mod switch;
+
+use bevy::prelude::*;
+
+use air_station::AirStationPlugin;
+use code::CodePlugin;
+use current::CurrentPlugin;
+use fan::FanPlugin;
+use gate::GatePlugin;
+use pressure_plate::PressurePlatePlugin;
+use prop::PropPlugin;
+use security_camera::SecurityCameraPlugin;
+use socket::SocketPlugin;
+use switch::SwitchPlugin;
+
+/// Every component a level can attach to its scene nodes.
+pub struct ComponentsPlugin;
+
+impl Plugin for ComponentsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            SecurityCameraPlugin,
+            SwitchPlugin,
+            PressurePlatePlugin,
+            GatePlugin,
+            CodePlugin,
+            SocketPlugin,
+            FanPlugin,
+            CurrentPlugin,
+            PropPlugin,
+            AirStationPlugin,
+        ));
+    }
+}
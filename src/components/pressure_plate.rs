@@ -0,0 +1,199 @@
+use std::collections::LinkedList;
+
+use bevy::prelude::*;
+use bevy_rapier2d::{
+    dynamics::{ReadMassProperties, RigidBody},
+    geometry::Collider,
+};
+use serde::Deserialize;
+
+use crate::{
+    checkpoint::{CheckpointAppExt, Checkpointed},
+    game_scene::SceneComponentAppExt,
+    sensor::{SensorContacts, Sensors},
+    simulation::SimulationSet,
+    validation::{Disabled, SceneWiringError},
+    wiring::{Wired, WiringAppExt},
+    GameState,
+};
+
+use super::loading::Loading;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlateMode {
+    /// Activated while weighed down.
+    #[default]
+    Momentary,
+    /// Stays activated once weighed down.
+    Latching,
+}
+
+#[derive(Event)]
+pub struct PlatePressed {
+    pub entity: Entity,
+    pub level: GameState,
+}
+
+/// Sensor pressed by the dynamic bodies on it, the player included.
+#[derive(Component, Debug)]
+pub struct PressurePlate {
+    pub mode: PlateMode,
+    /// Total mass of the bodies needed to press the plate.
+    pub min_mass: f32,
+    pressed: bool,
+    latched: bool,
+    sensor: Option<Entity>,
+    screens: Vec<(Entity, bool)>,
+}
+
+impl PressurePlate {
+    pub fn new(mode: PlateMode, min_mass: f32) -> Self {
+        Self {
+            mode,
+            min_mass,
+            pressed: false,
+            latched: false,
+            sensor: None,
+            screens: vec![],
+        }
+    }
+
+    pub fn activated(&self) -> bool {
+        self.pressed || self.latched
+    }
+}
+
+impl Wired for PressurePlate {
+    const OUTPUTS: &'static [&'static str] = &["activated"];
+    const INPUTS: &'static [&'static str] = &[];
+
+    fn output(&self, _port: &str) -> bool {
+        self.activated()
+    }
+
+    fn input(&mut self, _port: &str, _value: bool) {}
+}
+
+impl Checkpointed for PressurePlate {
+    type Snapshot = bool;
+
+    fn snapshot(&self) -> bool {
+        self.latched
+    }
+
+    fn restore(&mut self, latched: &bool) {
+        self.latched = *latched;
+    }
+}
+
+#[derive(Deserialize)]
+struct PlateExtras {
+    #[serde(default)]
+    mode: PlateMode,
+    #[serde(default)]
+    min_mass: f32,
+}
+
+pub struct PressurePlatePlugin;
+
+impl Plugin for PressurePlatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlatePressed>()
+            .add_wired::<PressurePlate>()
+            .add_checkpointed::<PressurePlate>()
+            .add_scene_component("pressure_plate", |entity, extras: PlateExtras, _| {
+                entity.insert(PressurePlate::new(extras.mode, extras.min_mass));
+                Ok(())
+            })
+            .add_systems(Update, init.run_if(any_with_component::<Loading>()))
+            .add_systems(
+                FixedUpdate,
+                update
+                    .in_set(SimulationSet::Interact)
+                    .run_if(any_with_component::<PressurePlate>())
+                    .run_if(not(any_with_component::<Loading>())),
+            );
+    }
+}
+
+fn init(
+    mut commands: Commands,
+    mut plates: Query<(Entity, &mut PressurePlate, &Name), With<Loading>>,
+    mut errors: EventWriter<SceneWiringError>,
+    parents: Query<&Children>,
+    names: Query<&Name>,
+    colliders: Query<&Collider>,
+    mesh_hs: Query<&Handle<Mesh>>,
+    state: Res<State<GameState>>,
+) {
+    for (entity, mut plate, plate_name) in plates.iter_mut() {
+        commands.entity(entity).remove::<Loading>();
+
+        let mut stack = LinkedList::from([entity]);
+        while let Some(current) = stack.pop_back() {
+            if let Ok(name) = names.get(current).map(Name::as_str) {
+                if name.contains("sensor") && colliders.get(current).is_ok() {
+                    plate.sensor = Some(current);
+                }
+                for (part, green) in [("red", false), ("green", true)] {
+                    if name.contains(part) && mesh_hs.get(current).is_ok() {
+                        plate.screens.push((current, green));
+                        commands.entity(current).try_insert(Visibility::Hidden);
+                    }
+                }
+            }
+            if let Ok(children) = parents.get(current) {
+                stack.extend(children.into_iter());
+            }
+        }
+
+        if plate.sensor.is_none() {
+            errors.send(
+                SceneWiringError::new(state.get(), plate_name, "a \"sensor\" collider")
+                    .disable(entity),
+            );
+        }
+    }
+}
+
+fn update(
+    mut plates: Query<(Entity, &mut PressurePlate), Without<Disabled>>,
+    mut visibility: Query<&mut Visibility>,
+    mut pressed: EventWriter<PlatePressed>,
+    bodies: Query<(&RigidBody, Option<&ReadMassProperties>)>,
+    sensors: Sensors,
+    state: Res<State<GameState>>,
+) {
+    for (entity, mut plate) in plates.iter_mut() {
+        let masses = sensors
+            .contacts(plate.sensor.unwrap())
+            .map(SensorContacts::bodies)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|body| bodies.get(body).ok())
+            .filter(|(body, _)| **body == RigidBody::Dynamic)
+            .map(|(_, mass)| mass.map_or(0.0, |mass| mass.get().mass))
+            .collect::<Vec<_>>();
+        let weighed_down = !masses.is_empty() && masses.iter().sum::<f32>() >= plate.min_mass;
+
+        if weighed_down && !plate.activated() {
+            pressed.send(PlatePressed {
+                entity,
+                level: state.get().clone(),
+            });
+        }
+        plate.pressed = weighed_down;
+        plate.latched |= weighed_down && plate.mode == PlateMode::Latching;
+
+        for &(screen, green) in &plate.screens {
+            if let Ok(mut visibility) = visibility.get_mut(screen) {
+                *visibility = if green == plate.activated() {
+                    Visibility::Visible
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_rapier2d::dynamics::Velocity;
+
+use crate::{
+    interaction::{Interactable, Interacted},
+    player::{Direction, Player, PlayerPhysics},
+    simulation::SimulationSet,
+};
+
+/// Distance in front of the diver at which a grabbed prop is held.
+const HOLD_DISTANCE: f32 = 1.5;
+/// Share of the distance to the hold point a grabbed prop closes per second.
+const HOLD_STIFFNESS: f32 = 8.0;
+
+/// Dynamic body made from a glTF node with the `"dynamic": true` extra. The player pushes it
+/// by swimming into it, currents carry it, and it can be grabbed and towed.
+#[derive(Component, Default)]
+pub struct Prop {
+    pub grabbed: bool,
+}
+
+/// Collider of the `Prop` on the given entity. It is the node's own collider or that of a
+/// mesh below it.
+#[derive(Component)]
+pub struct PropCollider(pub Entity);
+
+pub struct PropPlugin;
+
+impl Plugin for PropPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            grab.in_set(SimulationSet::Interact)
+                .run_if(any_with_component::<Prop>())
+                .run_if(resource_exists::<Player>()),
+        );
+    }
+}
+
+fn grab(
+    mut player: ResMut<Player>,
+    mut props: Query<(Entity, &mut Prop, &mut Velocity, &GlobalTransform)>,
+    mut colliders: Query<(&PropCollider, &mut Interactable)>,
+    mut interacted: EventReader<Interacted>,
+    player_physics: Query<&Transform, With<PlayerPhysics>>,
+) {
+    let used = interacted
+        .read()
+        .filter_map(|i| colliders.get(i.entity).ok())
+        .map(|(collider, _)| collider.0)
+        .collect::<HashSet<_>>();
+    let holding = props.iter().any(|(_, prop, ..)| prop.grabbed);
+    // A press that nothing else took lets go of the held prop.
+    let release = holding && used.is_empty() && player.interact.consume();
+
+    let Ok(player_transform) = player_physics.get_single() else {
+        return;
+    };
+    let facing = match player.direction {
+        Direction::Left => Vec2::NEG_X,
+        Direction::Right => Vec2::X,
+    };
+    let hold = player_transform.translation.xy() + facing * HOLD_DISTANCE;
+
+    for (entity, mut prop, mut velocity, transform) in props.iter_mut() {
+        if used.contains(&entity) {
+            prop.grabbed = !prop.grabbed;
+        } else if release {
+            prop.grabbed = false;
+        }
+        if prop.grabbed {
+            velocity.linvel = (hold - transform.translation().xy()) * HOLD_STIFFNESS;
+        }
+    }
+
+    let holding = props.iter().any(|(_, prop, ..)| prop.grabbed);
+    for (collider, mut interactable) in colliders.iter_mut() {
+        let Ok((_, prop, ..)) = props.get(collider.0) else {
+            continue;
+        };
+        interactable.enabled = prop.grabbed || !holding;
+        let prompt = if prop.grabbed { "let go" } else { "grab" };
+        if interactable.prompt != prompt {
+            interactable.prompt = prompt.to_string();
+        }
+    }
+}
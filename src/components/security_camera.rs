@@ -2,8 +2,18 @@ use std::{collections::LinkedList, f32::consts::FRAC_PI_2};
 
 use bevy::{pbr::ExtendedMaterial, prelude::*};
 use bevy_rapier2d::geometry::Collider;
+use serde::de::IgnoredAny;
 
-use crate::{materials::beam_material::BeamMaterial, player::PlayerCollision};
+use crate::{
+    checkpoint::{CheckpointAppExt, Checkpointed},
+    game_scene::SceneComponentAppExt,
+    materials::beam_material::BeamMaterial,
+    sensor::Sensors,
+    simulation::SimulationSet,
+    validation::{Disabled, SceneWiringError},
+    wiring::{Wired, WiringAppExt},
+    GameState,
+};
 
 use super::loading::Loading;
 
@@ -20,11 +30,18 @@ struct CamSensor {
     timer: f32,
 }
 
+#[derive(Event)]
+pub struct CameraAlarm {
+    pub entity: Entity,
+    pub level: GameState,
+}
+
 #[derive(Component)]
 pub struct SecurityCamera {
     pub active: bool,
     pub triggered: bool,
     pub wire: bool,
+    rearm: bool,
 }
 
 impl SecurityCamera {
@@ -33,29 +50,64 @@ impl SecurityCamera {
             active: true,
             triggered: false,
             wire: false,
+            rearm: false,
         }
     }
 }
 
+impl Wired for SecurityCamera {
+    const OUTPUTS: &'static [&'static str] = &["triggered"];
+    const INPUTS: &'static [&'static str] = &["enable"];
+
+    fn output(&self, _port: &str) -> bool {
+        self.triggered
+    }
+
+    fn input(&mut self, _port: &str, value: bool) {
+        self.active = value;
+    }
+}
+
+impl Checkpointed for SecurityCamera {
+    type Snapshot = bool;
+
+    fn snapshot(&self) -> bool {
+        self.active
+    }
+
+    fn restore(&mut self, active: &bool) {
+        self.active = *active;
+        self.triggered = false;
+        self.rearm = true;
+    }
+}
+
 pub struct SecurityCameraPlugin;
 
 impl Plugin for SecurityCameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                init.run_if(any_with_component::<Loading>()),
+        app.add_event::<CameraAlarm>()
+            .add_wired::<SecurityCamera>()
+            .add_checkpointed::<SecurityCamera>()
+            .add_scene_component("security_camera", |entity, _: IgnoredAny, _| {
+                entity.insert(SecurityCamera::new());
+                Ok(())
+            })
+            .add_systems(Update, init.run_if(any_with_component::<Loading>()))
+            .add_systems(
+                FixedUpdate,
                 update
+                    .in_set(SimulationSet::Interact)
                     .run_if(any_with_component::<CamSensor>())
                     .run_if(not(any_with_component::<Loading>())),
-            ),
-        );
+            );
     }
 }
 
 fn init(
     mut commands: Commands,
     mut camcone_materials: ResMut<Assets<ExtendedMaterial<StandardMaterial, BeamMaterial>>>,
+    mut errors: EventWriter<SceneWiringError>,
     mut cones: Query<(Entity, &mut CamCone), With<Loading>>,
     cameras: Query<(Entity, &Name), (With<Loading>, With<SecurityCamera>)>,
     materials: Res<Assets<StandardMaterial>>,
@@ -64,14 +116,18 @@ fn init(
     names: Query<&Name>,
     colliders: Query<&Collider>,
     mesh_hs: Query<&Handle<Mesh>>,
+    state: Res<State<GameState>>,
 ) {
     for (entity, camera_name) in cameras.iter() {
         commands.entity(entity).remove::<Loading>();
 
+        let mut cone = false;
+        let mut sensor = false;
         let mut stack = LinkedList::from([entity]);
         while let Some(current) = stack.pop_back() {
             if let Ok(name) = names.get(current).map(Name::as_str) {
                 if name.contains("cone") && mesh_hs.get(current).is_ok() {
+                    cone = true;
                     commands.entity(current).insert((
                         Loading,
                         CamCone {
@@ -82,6 +138,7 @@ fn init(
                     ));
                 }
                 if name.contains("sensor") && colliders.get(current).is_ok() {
+                    sensor = true;
                     commands.entity(current).insert(CamSensor {
                         camera_name: camera_name.to_string(),
                         timer: 0.0,
@@ -92,6 +149,19 @@ fn init(
                 stack.extend(children.into_iter());
             }
         }
+
+        let mut missing = vec![];
+        if !sensor {
+            missing.push("a \"sensor\" collider");
+        }
+        if !cone {
+            missing.push("a \"cone\" mesh");
+        }
+        if !missing.is_empty() {
+            errors.send(
+                SceneWiringError::new(state.get(), camera_name, missing.join(", ")).disable(entity),
+            );
+        }
     }
 
     for (entity, mut cone) in cones.iter_mut() {
@@ -133,32 +203,38 @@ fn init(
 }
 
 fn update(
-    mut cameras: Query<(&mut SecurityCamera, &Name)>,
+    mut cameras: Query<(Entity, &mut SecurityCamera, &Name), Without<Disabled>>,
+    mut alarm: EventWriter<CameraAlarm>,
     mut sensors: Query<(Entity, &mut CamSensor)>,
     mut spotlights: Query<&mut SpotLight>,
     mut camcone_materials: ResMut<Assets<ExtendedMaterial<StandardMaterial, BeamMaterial>>>,
     cones: Query<&CamCone>,
-    collisions: Query<&PlayerCollision>,
+    contacts: Sensors,
     time: Res<Time>,
+    state: Res<State<GameState>>,
 ) {
     let color_1 = Vec3::new(0.0, 1.0, 1.0);
     let color_2 = Vec3::new(1.0, 1.0, 0.0);
     let color_3 = Vec3::new(1.0, 0.0, 0.0);
 
     for (entity, mut sensor) in sensors.iter_mut() {
-        let Some((mut camera, camera_name)) = cameras
+        let Some((camera_entity, mut camera, camera_name)) = cameras
             .iter_mut()
-            .find(|(_, name)| name.as_str() == sensor.camera_name)
-            .map(|(camera, name)| (camera, name.as_str()))
+            .find(|(_, _, name)| name.as_str() == sensor.camera_name)
+            .map(|(e, camera, name)| (e, camera, name.as_str()))
         else {
             continue;
         };
 
+        if camera.rearm {
+            sensor.timer = 0.0;
+        }
+
         if camera.triggered {
             continue;
         }
 
-        let interacting = collisions.iter().find(|c| c.other == entity).is_some() || camera.wire;
+        let interacting = contacts.player_inside(entity) || camera.wire;
         if interacting && camera.active {
             sensor.timer += time.delta_seconds() * 0.2;
         } else {
@@ -168,6 +244,10 @@ fn update(
         if sensor.timer > 1.0 {
             camera.triggered = true;
             sensor.timer = 1.0;
+            alarm.send(CameraAlarm {
+                entity: camera_entity,
+                level: state.get().clone(),
+            });
         }
 
         for cone in cones.iter() {
@@ -199,4 +279,8 @@ fn update(
             light.intensity = material.extension.visibility * (40000.0 + sensor.timer * 160000.0);
         }
     }
+
+    for (_, mut camera, _) in cameras.iter_mut().filter(|(_, camera, _)| camera.rearm) {
+        camera.rearm = false;
+    }
 }
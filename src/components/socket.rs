@@ -6,16 +6,24 @@ use bevy_mod_raycast::{
     primitives::Ray3d,
 };
 use bevy_rapier2d::geometry::{Collider, Sensor};
+use serde::Deserialize;
 
 use crate::{
-    player::{Player, PlayerCollision, PlayerPhysics},
+    checkpoint::{CheckpointAppExt, Checkpointed},
+    game_scene::SceneComponentAppExt,
+    interaction::{Interactable, Interacted},
+    player::{Player, PlayerPhysics},
+    simulation::SimulationSet,
     utils::reduce_to_root,
+    validation::{Disabled, SceneWiringError},
+    wiring::{Wired, WiringAppExt},
+    GameState,
 };
 
 use super::{loading::Loading, security_camera::SecurityCamera};
 
-#[derive(Debug)]
-enum State {
+#[derive(Clone, Debug)]
+pub enum State {
     CanCarryFrom,
     CanCarryTo,
     Carrying,
@@ -23,12 +31,24 @@ enum State {
     ConnectedFrom,
 }
 
+#[derive(Event)]
+pub struct WireConnected {
+    pub entity: Entity,
+    pub to: Entity,
+    pub level: GameState,
+}
+
+#[derive(Event)]
+pub struct WireBroken {
+    pub entity: Entity,
+    pub level: GameState,
+}
+
 #[derive(Component, Debug)]
 pub struct Socket {
     sensor: Option<Entity>,
     wire: Option<Entity>,
     state: State,
-    is_action_last: bool,
     break_timer: f32,
     camera: Option<Entity>,
 }
@@ -43,7 +63,6 @@ impl Socket {
             } else {
                 State::CanCarryTo
             },
-            is_action_last: false,
             break_timer: 0.0,
             camera: None,
         }
@@ -57,32 +76,77 @@ impl Socket {
     }
 }
 
+impl Wired for Socket {
+    const OUTPUTS: &'static [&'static str] = &["connected"];
+    const INPUTS: &'static [&'static str] = &[];
+
+    fn output(&self, _port: &str) -> bool {
+        self.connected()
+    }
+
+    fn input(&mut self, _port: &str, _value: bool) {}
+}
+
+impl Checkpointed for Socket {
+    type Snapshot = State;
+
+    fn snapshot(&self) -> State {
+        self.state.clone()
+    }
+
+    fn restore(&mut self, state: &State) {
+        self.state = state.clone();
+        self.break_timer = 0.0;
+    }
+}
+
+#[derive(Deserialize)]
+struct SocketExtras {
+    start: bool,
+}
+
 pub struct SocketPlugin;
 
 impl Plugin for SocketPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                init.run_if(any_with_component::<Loading>()),
-                (update, wire)
+        app.add_event::<WireConnected>()
+            .add_event::<WireBroken>()
+            .add_wired::<Socket>()
+            .add_checkpointed::<Socket>()
+            .add_scene_component("socket", |entity, extras: SocketExtras, _| {
+                entity.insert(Socket::new(extras.start));
+                Ok(())
+            })
+            .add_systems(
+                Update,
+                (
+                    init.run_if(any_with_component::<Loading>()),
+                    wire.run_if(any_with_component::<Socket>())
+                        .run_if(not(any_with_component::<Loading>())),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                update
+                    .in_set(SimulationSet::Interact)
                     .run_if(any_with_component::<Socket>())
                     .run_if(not(any_with_component::<Loading>())),
-            ),
-        );
+            );
     }
 }
 
 fn init(
     mut commands: Commands,
-    mut sockets: Query<(Entity, &mut Socket), With<Loading>>,
+    mut sockets: Query<(Entity, &mut Socket, &Name), With<Loading>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut errors: EventWriter<SceneWiringError>,
     parents: Query<&Children>,
     names: Query<&Name>,
     colliders: Query<&Collider>,
+    state: Res<bevy::prelude::State<GameState>>,
 ) {
-    for (entity, mut socket) in sockets.iter_mut() {
+    for (entity, mut socket, socket_name) in sockets.iter_mut() {
         commands.entity(entity).remove::<Loading>();
 
         let mut stack = LinkedList::from([entity]);
@@ -97,65 +161,88 @@ fn init(
             }
         }
 
-        commands.entity(socket.sensor.unwrap()).with_children(|p| {
-            socket.wire = Some(
-                p.spawn((
-                    PbrBundle {
-                        mesh: meshes.add(Mesh::from(shape::Cylinder {
-                            ..Default::default()
-                        })),
-                        material: materials.add(StandardMaterial::default()),
-                        visibility: Visibility::Hidden,
-                        ..default()
-                    },
-                    NotShadowReceiver,
-                ))
-                .id(),
+        let Some(sensor) = socket.sensor else {
+            errors.send(
+                SceneWiringError::new(state.get(), socket_name, "a \"sensor\" collider")
+                    .disable(entity),
             );
-        });
+            continue;
+        };
+        commands
+            .entity(sensor)
+            .insert(Interactable::new("take the wire"))
+            .with_children(|p| {
+                socket.wire = Some(
+                    p.spawn((
+                        PbrBundle {
+                            mesh: meshes.add(Mesh::from(shape::Cylinder {
+                                ..Default::default()
+                            })),
+                            material: materials.add(StandardMaterial::default()),
+                            visibility: Visibility::Hidden,
+                            ..default()
+                        },
+                        NotShadowReceiver,
+                    ))
+                    .id(),
+                );
+            });
     }
 }
 
 fn update(
     mut player: ResMut<Player>,
-    mut sockets: Query<(Entity, &mut Socket)>,
+    mut sockets: Query<(Entity, &mut Socket), Without<Disabled>>,
     mut cams: Query<&mut SecurityCamera>,
+    mut interactables: Query<&mut Interactable>,
+    mut interacted: EventReader<Interacted>,
     mut raycast: Raycast,
+    mut connected: EventWriter<WireConnected>,
+    mut broken: EventWriter<WireBroken>,
     time: Res<Time>,
     transforms_g: Query<&GlobalTransform>,
-    collisions: Query<&PlayerCollision>,
+    player_physics: Query<&Transform, With<PlayerPhysics>>,
     parents: Query<&Children>,
     children: Query<&Parent>,
     wire_filter: Query<(), (With<Collider>, Without<Sensor>, Without<PlayerPhysics>)>,
+    state: Res<bevy::prelude::State<GameState>>,
 ) {
+    let interacted = interacted.read().map(|i| i.entity).collect::<Vec<_>>();
+    let carrying_any = sockets
+        .iter()
+        .any(|(_, socket)| matches!(socket.state, State::Carrying));
     let mut inside = None;
     let mut carrying = None;
-    let mut is_acted = false;
 
     for (entity, mut socket) in sockets.iter_mut() {
         let sensor = socket.sensor.unwrap();
-        let is_inside = collisions.iter().find(|c| c.other == sensor).is_some();
+        let used = interacted.contains(&sensor);
 
-        let acted = !socket.is_action_last && player.is_action;
-        socket.is_action_last = player.is_action;
-
-        match socket.state {
+        let (prompt, enabled) = match socket.state {
             State::CanCarryFrom => {
-                if is_inside && acted {
+                if used && !carrying_any {
                     socket.state = State::Carrying;
                     player.socket = Some(entity);
                 }
+                ("take the wire", !carrying_any)
             }
             State::CanCarryTo => {
-                if is_inside {
+                if used {
                     inside = Some(entity);
                 }
+                ("plug in the wire", carrying_any)
             }
             State::Carrying => {
                 carrying = Some(entity);
-                is_acted = acted;
+                ("", false)
+            }
+            _ => ("", false),
+        };
+        if let Ok(mut interactable) = interactables.get_mut(sensor) {
+            if interactable.prompt != prompt {
+                interactable.prompt = prompt.to_string();
             }
-            _ => {}
+            interactable.enabled = enabled;
         }
 
         if let Some(camera) = socket.camera {
@@ -169,10 +256,9 @@ fn update(
     };
 
     let (breaking, camera) = {
-        let from = transforms_g
-            .get(player.oxygen.unwrap())
-            .unwrap()
-            .transform_point(Vec3::ZERO);
+        // The simulated body rather than the animated model, so that the wire does not
+        // depend on the frame rate.
+        let from = player_physics.single().translation;
         let to = transforms_g
             .get(sockets.get(carrying).unwrap().1.sensor.unwrap())
             .unwrap()
@@ -244,26 +330,34 @@ fn update(
         if socket.break_timer >= 1.0 {
             socket.state = State::CanCarryFrom;
             socket.break_timer = 0.0;
+            broken.send(WireBroken {
+                entity: carrying,
+                level: state.get().clone(),
+            });
             return;
         }
         socket.break_timer = socket.break_timer.max(0.0).min(1.0);
     }
 
+    // A press that no socket took drops the wire.
+    if inside.is_none() && !player.interact.consume() {
+        return;
+    }
     match inside {
         Some(inside) => {
-            let [(_, mut carrying), (to, mut inside)] =
+            let [(from, mut carrying), (to, mut inside)] =
                 sockets.get_many_mut([carrying, inside]).unwrap();
-
-            if is_acted {
-                carrying.state = State::ConnectedTo(to);
-                inside.state = State::ConnectedFrom;
-            }
+            carrying.state = State::ConnectedTo(to);
+            inside.state = State::ConnectedFrom;
+            connected.send(WireConnected {
+                entity: from,
+                to,
+                level: state.get().clone(),
+            });
         }
         None => {
-            if is_acted {
-                let (_, mut carrying) = sockets.get_mut(carrying).unwrap();
-                carrying.state = State::CanCarryFrom;
-            }
+            let (_, mut carrying) = sockets.get_mut(carrying).unwrap();
+            carrying.state = State::CanCarryFrom;
         }
     }
 }
@@ -274,7 +368,7 @@ fn wire(
     mut materials: ResMut<Assets<StandardMaterial>>,
     material_hs: Query<&Handle<StandardMaterial>>,
     player: Res<Player>,
-    sockets: Query<&mut Socket>,
+    sockets: Query<&Socket, Without<Disabled>>,
     transforms_g: Query<&GlobalTransform>,
 ) {
     for socket in sockets.iter() {
@@ -1,12 +1,47 @@
-use std::collections::{HashMap, LinkedList};
+use std::collections::LinkedList;
 
 use bevy::prelude::*;
 use bevy_rapier2d::geometry::Collider;
+use serde::Deserialize;
 
-use crate::player::{Player, PlayerCollision};
+use crate::{
+    checkpoint::{CheckpointAppExt, Checkpointed},
+    game_scene::SceneComponentAppExt,
+    interaction::{Interactable, Interacted},
+    player::Player,
+    sensor::Sensors,
+    simulation::SimulationSet,
+    validation::{Disabled, SceneWiringError},
+    wiring::{Wired, WiringAppExt},
+    GameState,
+};
 
 use super::loading::Loading;
 
+/// Seconds a timed switch stays on without a `duration` extra.
+const TIMED_DURATION: f32 = 5.0;
+/// Seconds before a timed switch reverts during which its green screen blinks.
+const BLINK_TIME: f32 = 2.0;
+/// Blinks per second of the green screen.
+const BLINK_RATE: f32 = 4.0;
+/// Width of the countdown label over a timed switch, in logical pixels.
+const COUNTDOWN_WIDTH: f32 = 80.0;
+const COUNTDOWN_FONT_SIZE: f32 = 32.0;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchMode {
+    /// Stays on once pulled.
+    #[default]
+    OneShot,
+    /// Every pull flips it.
+    Toggle,
+    /// On while the interact action is held at the lever.
+    Momentary,
+    /// Reverts after its duration.
+    Timed,
+}
+
 enum ScreenKind {
     Red,
     Green,
@@ -21,45 +56,147 @@ struct SwitchScreen {
 #[derive(Component)]
 struct SwitchSensor(String);
 
+/// Label over the green screen of a switch with the seconds a timed switch has left.
+#[derive(Component)]
+pub struct SwitchCountdown {
+    pub switch: Entity,
+    screen: Entity,
+}
+
+#[derive(Event)]
+pub struct SwitchPulled {
+    pub entity: Entity,
+    pub level: GameState,
+}
+
 #[derive(Component)]
 pub struct Switch {
-    clicked: bool,
+    pub mode: SwitchMode,
+    /// Seconds a timed switch stays on.
+    pub duration: f32,
+    on: bool,
+    /// Seconds until a timed switch reverts.
+    remaining: f32,
     timer: f32,
+    /// The lever has to be put where a restored checkpoint left it.
+    rewind: bool,
     animation: Handle<AnimationClip>,
 }
 
 impl Switch {
-    pub fn new(animations: &HashMap<String, Handle<AnimationClip>>) -> Self {
+    pub fn new(animation: Handle<AnimationClip>) -> Self {
         Self {
-            clicked: false,
+            mode: SwitchMode::OneShot,
+            duration: TIMED_DURATION,
+            on: false,
+            remaining: 0.0,
             timer: 0.0,
-            animation: animations.get("switch_pull").unwrap().clone_weak(),
+            rewind: false,
+            animation,
         }
     }
 
+    pub fn with_mode(mut self, mode: SwitchMode, duration: Option<f32>) -> Self {
+        self.mode = mode;
+        self.duration = duration.unwrap_or(TIMED_DURATION);
+        self
+    }
+
+    /// Whether the lever is pulled, even if its animation has not got there yet.
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Seconds until a timed switch that is on reverts.
+    pub fn remaining(&self) -> Option<f32> {
+        (self.mode == SwitchMode::Timed && self.on).then_some(self.remaining)
+    }
+
     pub fn activated(&self) -> bool {
         self.timer >= 0.5
     }
+
+    fn set(&mut self, on: bool, player: &mut AnimationPlayer) {
+        if on == self.on {
+            return;
+        }
+        self.on = on;
+        self.remaining = self.duration;
+        // Continue from the current pose, so that a lever pushed back mid-pull does not jump.
+        let seek = player.seek_time().max(0.0);
+        player.play(self.animation.clone_weak()).replay();
+        player.seek_to(seek).set_speed(if on { 2.0 } else { -2.0 });
+    }
+}
+
+impl Wired for Switch {
+    const OUTPUTS: &'static [&'static str] = &["activated"];
+    const INPUTS: &'static [&'static str] = &[];
+
+    fn output(&self, _port: &str) -> bool {
+        self.activated()
+    }
+
+    fn input(&mut self, _port: &str, _value: bool) {}
+}
+
+impl Checkpointed for Switch {
+    type Snapshot = (bool, f32, f32);
+
+    fn snapshot(&self) -> (bool, f32, f32) {
+        (self.on, self.remaining, self.timer)
+    }
+
+    fn restore(&mut self, (on, remaining, timer): &(bool, f32, f32)) {
+        self.rewind = self.on != *on;
+        self.on = *on;
+        self.remaining = *remaining;
+        self.timer = *timer;
+    }
+}
+
+#[derive(Deserialize)]
+struct SwitchExtras {
+    #[serde(default)]
+    mode: SwitchMode,
+    #[serde(default)]
+    duration: Option<f32>,
 }
 
 pub struct SwitchPlugin;
 
 impl Plugin for SwitchPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                init.run_if(any_with_component::<Loading>()),
+        app.add_event::<SwitchPulled>()
+            .add_wired::<Switch>()
+            .add_checkpointed::<Switch>()
+            .add_scene_component("switch", |entity, extras: SwitchExtras, animations| {
+                let animation = animations
+                    .get("switch_pull")
+                    .ok_or_else(|| "an animation \"switch_pull\"".to_string())?;
+                entity.insert(
+                    Switch::new(animation.clone_weak()).with_mode(extras.mode, extras.duration),
+                );
+                Ok(())
+            })
+            .add_systems(Update, init.run_if(any_with_component::<Loading>()))
+            .add_systems(
+                Update,
+                countdown.run_if(any_with_component::<SwitchCountdown>()),
+            )
+            .add_systems(
+                FixedUpdate,
                 update
+                    .in_set(SimulationSet::Interact)
                     .run_if(any_with_component::<SwitchSensor>())
                     .run_if(not(any_with_component::<Loading>())),
-            ),
-        );
+            );
     }
 }
 
 fn init(
     mut commands: Commands,
+    mut errors: EventWriter<SceneWiringError>,
     mut screens: Query<(Entity, &mut Visibility), (With<Loading>, With<SwitchScreen>)>,
     switches: Query<(Entity, &Name), (With<Loading>, With<Switch>)>,
     materials: Res<Assets<StandardMaterial>>,
@@ -68,10 +205,12 @@ fn init(
     names: Query<&Name>,
     colliders: Query<&Collider>,
     mesh_hs: Query<&Handle<Mesh>>,
+    state: Res<State<GameState>>,
 ) {
     for (entity, switch_name) in switches.iter() {
         commands.entity(entity).remove::<Loading>();
 
+        let mut sensor = false;
         let mut stack = LinkedList::from([entity]);
         while let Some(current) = stack.pop_back() {
             if let Ok(name) = names.get(current).map(Name::as_str) {
@@ -98,17 +237,49 @@ fn init(
                             },
                         ))
                         .try_insert(Visibility::Hidden);
+                    commands.spawn((
+                        SwitchCountdown {
+                            switch: entity,
+                            screen: current,
+                        },
+                        TextBundle {
+                            visibility: Visibility::Hidden,
+                            ..TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font_size: COUNTDOWN_FONT_SIZE,
+                                    color: Color::GREEN,
+                                    ..Default::default()
+                                },
+                            )
+                            .with_style(Style {
+                                position_type: PositionType::Absolute,
+                                width: Val::Px(COUNTDOWN_WIDTH),
+                                ..Default::default()
+                            })
+                            .with_text_alignment(TextAlignment::Center)
+                        },
+                    ));
                 }
                 if name.contains("sensor") && colliders.get(current).is_ok() {
-                    commands
-                        .entity(current)
-                        .insert(SwitchSensor(switch_name.to_string()));
+                    sensor = true;
+                    commands.entity(current).insert((
+                        SwitchSensor(switch_name.to_string()),
+                        Interactable::new("pull the lever"),
+                    ));
                 }
             }
             if let Ok(children) = parents.get(current) {
                 stack.extend(children.into_iter());
             }
         }
+
+        if !sensor {
+            errors.send(
+                SceneWiringError::new(state.get(), switch_name, "a \"sensor\" collider")
+                    .disable(entity),
+            );
+        }
     }
 
     for (entity, mut visibility) in screens.iter_mut() {
@@ -126,37 +297,82 @@ fn init(
 }
 
 fn update(
-    mut switches: Query<(&mut Switch, &mut AnimationPlayer, &Name)>,
+    mut switches: Query<(Entity, &mut Switch, &mut AnimationPlayer, &Name), Without<Disabled>>,
+    mut pulled: EventWriter<SwitchPulled>,
     mut screens: Query<(&SwitchScreen, &mut Visibility)>,
-    sensors: Query<(Entity, &SwitchSensor)>,
-    player: Res<Player>,
-    collisions: Query<&PlayerCollision>,
+    mut sensors: Query<(Entity, &SwitchSensor, &mut Interactable)>,
+    mut interacted: EventReader<Interacted>,
+    player: Option<Res<Player>>,
+    contacts: Sensors,
     time: Res<Time>,
+    state: Res<State<GameState>>,
 ) {
-    for (entity, sensor) in sensors.iter() {
-        let Some((mut switch, mut animation_player, switch_name)) = switches
+    let interacted = interacted.read().map(|i| i.entity).collect::<Vec<_>>();
+    for (entity, sensor, mut interactable) in sensors.iter_mut() {
+        let Some((switch_entity, mut switch, mut animation_player, switch_name)) = switches
             .iter_mut()
-            .find(|(_, _, name)| name.as_str() == sensor.0)
-            .map(|(switch, animation_player, name)| (switch, animation_player, name.as_str()))
+            .find(|(_, _, _, name)| name.as_str() == sensor.0)
+            .map(|(e, switch, animation_player, name)| {
+                (e, switch, animation_player, name.as_str())
+            })
         else {
             continue;
         };
 
-        let clicked = collisions.iter().find(|c| c.other == entity).is_some() && player.is_action;
-
-        if clicked && !switch.clicked {
-            animation_player
-                .play(switch.animation.clone_weak())
-                .set_speed(2.0);
+        if switch.rewind {
+            switch.rewind = false;
+            let animation = animation_player.play(switch.animation.clone_weak());
+            if switch.on {
+                animation.replay();
+                animation.set_speed(2.0);
+            } else {
+                animation.seek_to(0.0).pause();
+            }
         }
 
-        switch.clicked |= clicked;
+        let pulled_now = interacted.contains(&entity);
+        let on = match switch.mode {
+            SwitchMode::OneShot => switch.on || pulled_now,
+            SwitchMode::Toggle => switch.on != pulled_now,
+            SwitchMode::Momentary => {
+                let held = player.as_ref().map_or(false, |p| p.interact.held());
+                pulled_now || (switch.on && held && contacts.player_inside(entity))
+            }
+            SwitchMode::Timed => {
+                switch.remaining -= time.delta_seconds();
+                pulled_now || (switch.on && switch.remaining > 0.0)
+            }
+        };
 
-        if switch.clicked {
-            switch.timer += time.delta_seconds() * 2.0;
+        if on && !switch.on {
+            pulled.send(SwitchPulled {
+                entity: switch_entity,
+                level: state.get().clone(),
+            });
         }
+        switch.set(on, &mut animation_player);
+
+        interactable.enabled = match switch.mode {
+            SwitchMode::OneShot | SwitchMode::Timed | SwitchMode::Momentary => !switch.on,
+            SwitchMode::Toggle => true,
+        };
+        let prompt = match (switch.mode, switch.on) {
+            (SwitchMode::Momentary, _) => "hold the lever",
+            (_, true) => "push the lever back",
+            (_, false) => "pull the lever",
+        };
+        if interactable.prompt != prompt {
+            interactable.prompt = prompt.to_string();
+        }
+
+        let direction = if switch.on { 1.0 } else { -1.0 };
+        switch.timer += direction * time.delta_seconds() * 2.0;
         switch.timer = switch.timer.max(0.0).min(1.0);
 
+        let blink = switch.remaining().map_or(false, |remaining| {
+            remaining < BLINK_TIME && (remaining * BLINK_RATE).fract() < 0.5
+        });
+
         for (screen, mut visibility) in screens.iter_mut() {
             if screen.switch_name != switch_name {
                 continue;
@@ -171,7 +387,7 @@ fn update(
                     }
                 }
                 ScreenKind::Green => {
-                    if switch.activated() {
+                    if switch.activated() && !blink {
                         *visibility = Visibility::Visible
                     }
                 }
@@ -179,3 +395,49 @@ fn update(
         }
     }
 }
+
+/// Shows the seconds a timed switch has left over its green screen.
+fn countdown(
+    mut commands: Commands,
+    mut labels: Query<(
+        Entity,
+        &SwitchCountdown,
+        &mut Text,
+        &mut Style,
+        &mut Visibility,
+    )>,
+    switches: Query<&Switch>,
+    transforms: Query<&GlobalTransform>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+) {
+    // The second camera only draws the overlay layer on top of the first.
+    let camera = cameras.iter().find(|(camera, _)| camera.order == 0);
+    for (label, countdown, mut text, mut style, mut visibility) in labels.iter_mut() {
+        // The label outlives its level otherwise.
+        let Ok(switch) = switches.get(countdown.switch) else {
+            commands.entity(label).despawn_recursive();
+            continue;
+        };
+        let Some(remaining) = switch.remaining() else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        let value = format!("{}", remaining.max(0.0).ceil());
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+
+        let position = camera.zip(transforms.get(countdown.screen).ok()).and_then(
+            |((camera, camera_transform), screen)| {
+                camera.world_to_viewport(camera_transform, screen.translation())
+            },
+        );
+        let Some(position) = position else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        style.left = Val::Px(position.x - COUNTDOWN_WIDTH / 2.0);
+        style.top = Val::Px(position.y - COUNTDOWN_FONT_SIZE / 2.0);
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
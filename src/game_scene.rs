@@ -1,15 +1,35 @@
 use std::collections::HashMap;
 
 use bevy::{
+    ecs::system::EntityCommands,
     gltf::{Gltf, GltfExtras},
     pbr::{ExtendedMaterial, NotShadowCaster, NotShadowReceiver, OpaqueRendererMethod, TransmittedShadowReceiver},
     prelude::*,
     render::{mesh::VertexAttributeValues, primitives::Aabb, view::RenderLayers},
 };
-use bevy_rapier2d::geometry::{ActiveEvents, Collider, Sensor};
-use serde::Deserialize;
+use bevy_rapier2d::{
+    dynamics::{Damping, LockedAxes, ReadMassProperties, RigidBody, Velocity},
+    geometry::{ActiveEvents, Collider, ColliderMassProperties, Sensor},
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    checkpoint::Checkpoint,
+    components::{
+        loading::Loading,
+        prop::{Prop, PropCollider},
+    },
+    interaction::Interactable,
+    materials::paint_material::PaintMaterial,
+    sensor::SensorContacts,
+    utils::reduce_to_root,
+    validation::SceneWiringError,
+    GameState,
+};
 
-use crate::{materials::paint_material::PaintMaterial, utils::reduce_to_root};
+/// Linear and angular damping of props without a `drag` extra.
+const PROP_DRAG: f32 = 2.0;
 
 pub struct GameSceneData {
     pub root: Entity,
@@ -24,7 +44,111 @@ pub struct GameScenePlugin;
 
 impl Plugin for GameScenePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, load.run_if(any_with_component::<LoadGameScene>()));
+        app.init_resource::<SceneComponents>()
+            .add_systems(Update, load.run_if(any_with_component::<LoadGameScene>()));
+    }
+}
+
+type InsertComponent = Box<
+    dyn Fn(
+            &mut EntityCommands,
+            Value,
+            &HashMap<String, Handle<AnimationClip>>,
+        ) -> Result<(), String>
+        + Send
+        + Sync,
+>;
+
+/// Components that can be attached to a node with `{"component": "<name>", ...}` in its glTF
+/// extras. An optional `id` renames the node, e.g. for the wiring.
+#[derive(Resource, Default)]
+pub struct SceneComponents(HashMap<String, InsertComponent>);
+
+impl SceneComponents {
+    /// Inserts the component registered as `name` together with `Loading`, configured by
+    /// `extras`, or describes what is wrong with them.
+    pub fn insert(
+        &self,
+        entity: &mut EntityCommands,
+        name: &str,
+        extras: Map<String, Value>,
+        animations: &HashMap<String, Handle<AnimationClip>>,
+    ) -> Result<(), String> {
+        let Some(insert) = self.0.get(name) else {
+            return Err(format!("a registered component instead of \"{name}\""));
+        };
+        insert(entity, Value::Object(extras), animations)?;
+        entity.insert(Loading);
+        Ok(())
+    }
+
+    /// Inserts the component named in the `component` extra, if any.
+    fn insert_from_extras(
+        &self,
+        entity: &mut EntityCommands,
+        mut extras: Map<String, Value>,
+        animations: &HashMap<String, Handle<AnimationClip>>,
+    ) -> Result<(), String> {
+        let Some(component) = extras.remove("component") else {
+            return Ok(());
+        };
+        let Value::String(name) = component else {
+            return Err(format!("a component name instead of {component}"));
+        };
+        match extras.remove("id") {
+            Some(Value::String(id)) => {
+                entity.insert(Name::new(id));
+            }
+            Some(id) => return Err(format!("a string id instead of {id}")),
+            None => {}
+        }
+        self.insert(entity, &name, extras, animations)
+    }
+}
+
+pub trait SceneComponentAppExt {
+    /// Registers a component for the `component` extra. The other extras of the node are
+    /// deserialized into `P`.
+    fn add_scene_component<P: DeserializeOwned>(
+        &mut self,
+        name: &str,
+        insert: impl Fn(
+                &mut EntityCommands,
+                P,
+                &HashMap<String, Handle<AnimationClip>>,
+            ) -> Result<(), String>
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self;
+}
+
+impl SceneComponentAppExt for App {
+    fn add_scene_component<P: DeserializeOwned>(
+        &mut self,
+        name: &str,
+        insert: impl Fn(
+                &mut EntityCommands,
+                P,
+                &HashMap<String, Handle<AnimationClip>>,
+            ) -> Result<(), String>
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self {
+        let component = name.to_string();
+        self.world
+            .get_resource_or_insert_with(SceneComponents::default)
+            .0
+            .insert(
+                name.to_string(),
+                Box::new(move |entity, extras, animations| {
+                    let props = serde_json::from_value(extras)
+                        .map_err(|e| format!("valid \"{component}\" extras ({e})"))?;
+                    insert(entity, props, animations)
+                }),
+            );
+        self
     }
 }
 
@@ -51,8 +175,9 @@ impl LoadGameScene {
     }
 }
 
+/// Per-node settings from the glTF extras. They are inherited by all descendants.
 #[derive(Default, Debug, Clone, Deserialize, Component)]
-struct CustomProps {
+pub(crate) struct CustomProps {
     #[serde(default)]
     ignore_physics: bool,
     #[serde(default)]
@@ -69,6 +194,55 @@ struct CustomProps {
     complex_physics: bool,
     #[serde(default)]
     text: bool,
+    #[serde(default)]
+    checkpoint: bool,
+    /// Turns the node into a `Prop`, grabbed by the colliders of its meshes. Unlike the other
+    /// props, it is not inherited.
+    #[serde(default)]
+    dynamic: bool,
+    #[serde(default)]
+    mass: Option<f32>,
+    #[serde(default)]
+    drag: Option<f32>,
+}
+
+impl CustomProps {
+    pub(crate) const KEYS: &'static [&'static str] = &[
+        "ignore_physics",
+        "invisible",
+        "sensor",
+        "diffuse_transmission",
+        "no_shadow",
+        "color",
+        "complex_physics",
+        "text",
+        "checkpoint",
+        "dynamic",
+        "mass",
+        "drag",
+    ];
+
+    pub(crate) fn inherit(&self, ancestor: &Self) -> Self {
+        Self {
+            ignore_physics: self.ignore_physics || ancestor.ignore_physics,
+            invisible: self.invisible || ancestor.invisible,
+            sensor: self.sensor || ancestor.sensor,
+            diffuse_transmission: self.diffuse_transmission || ancestor.diffuse_transmission,
+            no_shadow: self.no_shadow || ancestor.no_shadow,
+            color: self.color,
+            complex_physics: self.complex_physics || ancestor.complex_physics,
+            text: self.text || ancestor.text,
+            checkpoint: self.checkpoint || ancestor.checkpoint,
+            dynamic: self.dynamic,
+            mass: self.mass,
+            drag: self.drag,
+        }
+    }
+
+    /// Whether a mesh with these props gets a collider, given the z range of its world bounds.
+    pub(crate) fn has_collider(&self, z_min: f32, z_max: f32) -> bool {
+        !self.ignore_physics && (self.complex_physics || (z_min <= 0.0 && z_max >= 0.0))
+    }
 }
 
 fn load(
@@ -87,6 +261,11 @@ fn load(
     mesh_hs: Query<&Handle<Mesh>>,
     aabbs: Query<(&Aabb, &GlobalTransform)>,
     names: Query<&Name>,
+    (components, state, mut errors): (
+        Res<SceneComponents>,
+        Res<State<GameState>>,
+        EventWriter<SceneWiringError>,
+    ),
 ) {
     for (root, mut scene) in scenes.iter_mut() {
         let gltf = match scene.gltf {
@@ -120,6 +299,11 @@ fn load(
             continue;
         };
 
+        let animations = gltf
+            .named_animations
+            .clone()
+            .into_iter()
+            .collect::<HashMap<_, _>>();
         let mut all_props = HashMap::<Entity, CustomProps>::new();
         let mut prop = |entity| {
             all_props
@@ -140,19 +324,26 @@ fn load(
             }
 
             let props = reduce_to_root(&children, entity, prop(entity).clone(), |props, r| {
-                let p = prop(r);
-                CustomProps {
-                    ignore_physics: p.ignore_physics || props.ignore_physics,
-                    invisible: p.invisible || props.invisible,
-                    sensor: p.sensor || props.sensor,
-                    diffuse_transmission: p.diffuse_transmission || props.diffuse_transmission,
-                    no_shadow: p.no_shadow || props.no_shadow,
-                    color: props.color,
-                    complex_physics: p.complex_physics || props.complex_physics,
-                    text: p.text || props.text,
-                }
+                props.inherit(&prop(r))
+            });
+            // glTF puts the meshes, and so the colliders, of a `dynamic` node below it.
+            let body = reduce_to_root(&children, entity, None, |body, r| {
+                body.or_else(|| prop(r).dynamic.then_some(r))
             });
 
+            if let Some(map) = extras
+                .get(entity)
+                .ok()
+                .and_then(|extras| serde_json::from_str::<Map<String, Value>>(&extras.value).ok())
+            {
+                if let Err(expected) =
+                    components.insert_from_extras(&mut commands.entity(entity), map, &animations)
+                {
+                    let name = names.get(entity).map_or("", Name::as_str);
+                    errors.send(SceneWiringError::new(state.get(), name, expected));
+                }
+            }
+
             if let Ok(mut light) = lights.get_mut(entity) {
                 light.shadows_enabled = true;
                 light.range = 1000.0;
@@ -175,7 +366,7 @@ fn load(
                 }
             }
 
-            if props.invisible || props.sensor {
+            if props.invisible || props.sensor || props.checkpoint {
                 commands.entity(entity).insert(Visibility::Hidden);
             }
 
@@ -189,6 +380,22 @@ fn load(
                     .insert((NotShadowCaster, NotShadowReceiver));
             }
 
+            if props.dynamic && !props.ignore_physics {
+                let drag = props.drag.unwrap_or(PROP_DRAG);
+                commands.entity(entity).insert((
+                    Prop::default(),
+                    RigidBody::Dynamic,
+                    // The 2D body would lose any other rotation of the node.
+                    LockedAxes::ROTATION_LOCKED,
+                    Damping {
+                        linear_damping: drag,
+                        angular_damping: drag,
+                    },
+                    Velocity::default(),
+                    ReadMassProperties::default(),
+                ));
+            }
+
             if !props.ignore_physics {
                 let new_entity = if props.complex_physics {
                     if let Ok(mesh) = mesh_hs.get(entity) {
@@ -253,8 +460,26 @@ fn load(
                 };
                 if let Some(mut new_entity) = new_entity {
                     new_entity.set_parent(entity);
-                    if props.sensor {
-                        new_entity.insert((Sensor, ActiveEvents::COLLISION_EVENTS));
+                    if props.sensor || props.checkpoint {
+                        new_entity.insert((
+                            Sensor,
+                            ActiveEvents::COLLISION_EVENTS,
+                            SensorContacts::default(),
+                        ));
+                    }
+                    if props.checkpoint {
+                        new_entity.insert(Checkpoint);
+                    }
+                    if let Some(body) = body {
+                        new_entity.insert((
+                            PropCollider(body),
+                            ActiveEvents::COLLISION_EVENTS,
+                            SensorContacts::default(),
+                            Interactable::new("grab"),
+                        ));
+                        if let Some(mass) = prop(body).mass {
+                            new_entity.insert(ColliderMassProperties::Mass(mass));
+                        }
                     }
                     if let Ok(name) = names.get(entity) {
                         new_entity.insert(name.clone());
@@ -263,13 +488,7 @@ fn load(
             }
         }
 
-        scene.on_ready.take().unwrap()(
-            &mut commands,
-            GameSceneData {
-                root,
-                animations: gltf.named_animations.clone().into_iter().collect(),
-            },
-        );
+        scene.on_ready.take().unwrap()(&mut commands, GameSceneData { root, animations });
         commands.entity(root).remove::<LoadGameScene>();
     }
 }
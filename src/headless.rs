@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{AppExit, PluginGroupBuilder, ScheduleRunnerPlugin},
+    core::FrameCount,
+    prelude::*,
+    render::{
+        pipelined_rendering::PipelinedRenderingPlugin,
+        settings::{RenderCreation, WgpuSettings},
+        RenderPlugin,
+    },
+    time::TimeUpdateStrategy,
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+
+use crate::simulation::TIMESTEP;
+
+/// `DefaultPlugins` without a window and without a GPU. This is used instead of `MinimalPlugins`
+/// with the asset and scene plugins because the glTF loader and the game's own materials need
+/// the mesh, image and material assets the render, PBR and UI plugins register. With no wgpu
+/// backend those plugins never look for a GPU, so nothing is rendered.
+pub fn headless_plugins() -> PluginGroupBuilder {
+    DefaultPlugins
+        .build()
+        .disable::<WinitPlugin>()
+        .disable::<PipelinedRenderingPlugin>()
+        .set(WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            ..Default::default()
+        })
+        .set(RenderPlugin {
+            render_creation: RenderCreation::Automatic(WgpuSettings {
+                backends: None,
+                ..Default::default()
+            }),
+        })
+        .add(ScheduleRunnerPlugin::run_loop(Duration::ZERO))
+}
+
+/// Advances time by exactly one simulation step every frame, so runs are reproducible.
+pub struct LockstepPlugin;
+
+impl Plugin for LockstepPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            TIMESTEP,
+        )));
+    }
+}
+
+pub struct HeadlessPlugin {
+    /// Exit after this many frames.
+    pub frames: Option<u32>,
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<LockstepPlugin>() {
+            app.add_plugins(LockstepPlugin);
+        }
+
+        if let Some(frames) = self.frames {
+            app.add_systems(
+                PostUpdate,
+                move |frame: Res<FrameCount>, mut exit: EventWriter<AppExit>| {
+                    if frame.0 >= frames {
+                        exit.send(AppExit);
+                    }
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, fs,
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use bevy::{input::InputSystem, prelude::*};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+/// Something the player can do, bound to keys and buttons by the `InputMap`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Interact,
+    Flashlight,
+    Click,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Interact,
+        Action::Flashlight,
+        Action::Click,
+        Action::Pause,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Interact => "Interact",
+            Action::Flashlight => "Flashlight",
+            Action::Click => "Click",
+            Action::Pause => "Pause",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Button of any connected gamepad.
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    pub fn is_gamepad(&self) -> bool {
+        matches!(self, Binding::Gamepad(_))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{key:?}"),
+            Binding::Mouse(MouseButton::Other(button)) => write!(f, "Mouse {button}"),
+            Binding::Mouse(button) => write!(f, "Mouse {button:?}"),
+            Binding::Gamepad(button) => write!(f, "Gamepad {button:?}"),
+        }
+    }
+}
+
+/// Bindings of every action, read from `input.json` in the config folder.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct InputMap(pub BTreeMap<Action, Vec<Binding>>);
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::{Gamepad, Key, Mouse};
+        use GamepadButtonType::*;
+        Self(BTreeMap::from([
+            (Action::MoveUp, vec![Key(KeyCode::W), Gamepad(DPadUp)]),
+            (Action::MoveDown, vec![Key(KeyCode::S), Gamepad(DPadDown)]),
+            (Action::MoveLeft, vec![Key(KeyCode::A), Gamepad(DPadLeft)]),
+            (Action::MoveRight, vec![Key(KeyCode::D), Gamepad(DPadRight)]),
+            (Action::Interact, vec![Key(KeyCode::E), Gamepad(South)]),
+            (
+                Action::Flashlight,
+                vec![Key(KeyCode::Space), Gamepad(RightTrigger2)],
+            ),
+            (Action::Click, vec![Mouse(MouseButton::Left), Gamepad(West)]),
+            (Action::Pause, vec![Key(KeyCode::Escape), Gamepad(Start)]),
+        ]))
+    }
+}
+
+impl InputMap {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    fn path() -> PathBuf {
+        ProjectDirs::from("", "", "twg-2024-1")
+            .map(|dirs| dirs.config_dir().to_path_buf())
+            .unwrap_or_default()
+            .join("input.json")
+    }
+
+    /// The config file, with default bindings for the actions it does not mention.
+    pub fn load() -> Self {
+        let path = Self::path();
+        let Ok(data) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match serde_json::from_str::<Self>(&data) {
+            Ok(mut map) => {
+                for (action, bindings) in Self::default().0 {
+                    map.0.entry(action).or_insert(bindings);
+                }
+                map
+            }
+            Err(e) => {
+                warn!("Input config '{}' is invalid: {e}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn write(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Actions held and pressed this frame.
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    stick: Vec2,
+    gamepad: bool,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Left stick of the gamepad that tilts it the furthest, after the dead zone.
+    pub fn stick(&self) -> Vec2 {
+        self.stick
+    }
+
+    /// Whether the last button pressed or stick tilted was on a gamepad rather than the
+    /// keyboard or mouse.
+    pub fn using_gamepad(&self) -> bool {
+        self.gamepad
+    }
+
+    /// Binding of `action` to show the player, one of the device they use if there is any.
+    pub fn binding(&self, map: &InputMap, action: Action) -> Option<Binding> {
+        let bindings = map.bindings(action);
+        bindings
+            .iter()
+            .find(|binding| binding.is_gamepad() == self.gamepad)
+            .or(bindings.first())
+            .copied()
+    }
+}
+
+/// Updates `ActionState` in `PreUpdate`.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionSet;
+
+pub struct InputMapPlugin {
+    /// Read the bindings from `input.json` and write them back when they change. Off keeps
+    /// the default bindings, e.g. in tests.
+    pub persist: bool,
+}
+
+impl Default for InputMapPlugin {
+    fn default() -> Self {
+        Self { persist: true }
+    }
+}
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        if self.persist {
+            app.insert_resource(InputMap::load())
+                .add_systems(Update, write.run_if(resource_changed::<InputMap>()));
+        } else {
+            app.init_resource::<InputMap>();
+        }
+
+        app.init_resource::<ActionState>()
+            .configure_sets(PreUpdate, ActionSet.after(InputSystem))
+            .add_systems(PreUpdate, update_actions.in_set(ActionSet));
+    }
+}
+
+fn write(map: Res<InputMap>) {
+    if map.is_added() {
+        return;
+    }
+    if let Err(e) = map.write() {
+        error!("Failed to write input config: {e:#}");
+    }
+}
+
+fn update_actions(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    map: Res<InputMap>,
+    mut actions: ResMut<ActionState>,
+) {
+    let pressed = |binding: &Binding| match *binding {
+        Binding::Key(key) => keyboard_input.pressed(key),
+        Binding::Mouse(button) => mouse_input.pressed(button),
+        Binding::Gamepad(button) => gamepads
+            .iter()
+            .any(|gamepad| gamepad_input.pressed(GamepadButton::new(gamepad, button))),
+    };
+    let just_pressed = |binding: &Binding| match *binding {
+        Binding::Key(key) => keyboard_input.just_pressed(key),
+        Binding::Mouse(button) => mouse_input.just_pressed(button),
+        Binding::Gamepad(button) => gamepads
+            .iter()
+            .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, button))),
+    };
+
+    actions.pressed.clear();
+    actions.just_pressed.clear();
+    for action in Action::ALL {
+        let bindings = map.bindings(action);
+        if bindings.iter().any(pressed) {
+            actions.pressed.insert(action);
+        }
+        if bindings.iter().any(just_pressed) {
+            actions.just_pressed.insert(action);
+        }
+    }
+
+    let axis = |gamepad, axis| {
+        gamepad_axes
+            .get(GamepadAxis::new(gamepad, axis))
+            .unwrap_or_default()
+    };
+    actions.stick = gamepads
+        .iter()
+        .map(|gamepad| {
+            Vec2::new(
+                axis(gamepad, GamepadAxisType::LeftStickX),
+                axis(gamepad, GamepadAxisType::LeftStickY),
+            )
+        })
+        .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        .unwrap_or_default();
+
+    if keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+    {
+        actions.gamepad = false;
+    }
+    if gamepad_input.get_just_pressed().next().is_some() || actions.stick != Vec2::ZERO {
+        actions.gamepad = true;
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::loading::Loading,
+    input::{Action, ActionState, InputMap},
+    player::{Player, PlayerPhysics},
+    sensor::{track_contacts, Sensors},
+    simulation::SimulationSet,
+};
+
+/// Sensor the player can use with the interact action.
+#[derive(Component)]
+pub struct Interactable {
+    /// What interacting does, e.g. `pull the lever`.
+    pub prompt: String,
+    /// Wins over overlapping interactables with a lower priority.
+    pub priority: i32,
+    pub enabled: bool,
+}
+
+impl Interactable {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            priority: 0,
+            enabled: true,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// The player used the interactable `entity` in this simulation step.
+#[derive(Event)]
+pub struct Interacted {
+    pub entity: Entity,
+}
+
+/// Interactable that a press would use right now.
+#[derive(Resource, Default, PartialEq)]
+pub struct InteractionTarget(pub Option<Entity>);
+
+#[derive(Component)]
+struct Prompt;
+
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Interacted>()
+            .init_resource::<InteractionTarget>()
+            .add_systems(
+                FixedUpdate,
+                dispatch
+                    .in_set(SimulationSet::Collide)
+                    .after(track_contacts)
+                    .run_if(resource_exists::<Player>())
+                    .run_if(not(any_with_component::<Loading>())),
+            )
+            .add_systems(Startup, spawn_prompt)
+            .add_systems(Update, prompt);
+    }
+}
+
+/// Picks the best enabled interactable the player overlaps and hands it the press, if any.
+/// While a view controller has the camera, presses are left to it.
+fn dispatch(
+    mut player: ResMut<Player>,
+    mut target: ResMut<InteractionTarget>,
+    mut interacted: EventWriter<Interacted>,
+    interactables: Query<(Entity, &Interactable, &GlobalTransform)>,
+    sensors: Sensors,
+    player_physics: Query<&Transform, With<PlayerPhysics>>,
+) {
+    let Ok(transform) = player_physics.get_single() else {
+        return;
+    };
+    let position = transform.translation;
+    let best = interactables
+        .iter()
+        .filter(|(entity, interactable, _)| interactable.enabled && sensors.player_inside(*entity))
+        .max_by(|(_, a, a_transform), (_, b, b_transform)| {
+            a.priority.cmp(&b.priority).then(
+                b_transform
+                    .translation()
+                    .distance_squared(position)
+                    .total_cmp(&a_transform.translation().distance_squared(position)),
+            )
+        })
+        .map(|(entity, ..)| entity)
+        .filter(|_| player.view_controller.is_none());
+
+    target.set_if_neq(InteractionTarget(best));
+    if let Some(entity) = best {
+        if player.interact.consume() {
+            interacted.send(Interacted { entity });
+        }
+    }
+}
+
+fn spawn_prompt(mut commands: Commands) {
+    commands.spawn((
+        Prompt,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 28.0,
+                color: Color::WHITE,
+                ..Default::default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(15.0),
+            width: Val::Percent(100.0),
+            ..Default::default()
+        })
+        .with_text_alignment(TextAlignment::Center),
+    ));
+}
+
+fn prompt(
+    mut prompts: Query<(&mut Text, &mut Visibility), With<Prompt>>,
+    target: Res<InteractionTarget>,
+    interactables: Query<&Interactable>,
+    player: Option<Res<Player>>,
+    map: Res<InputMap>,
+    actions: Res<ActionState>,
+) {
+    let interactable = target
+        .0
+        .filter(|_| player.is_some())
+        .and_then(|entity| interactables.get(entity).ok())
+        .filter(|interactable| interactable.enabled);
+
+    for (mut text, mut visibility) in prompts.iter_mut() {
+        let Some(interactable) = interactable else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        let key = actions
+            .binding(&map, Action::Interact)
+            .map(|binding| binding.to_string())
+            .unwrap_or_default();
+        let value = format!("{key} \u{2014} {}", interactable.prompt);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
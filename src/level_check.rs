@@ -0,0 +1,477 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use bevy::{asset::io::file::FileAssetReader, ecs::system::CommandQueue, prelude::*};
+use serde_json::{Map, Value};
+
+use crate::{
+    components::{code, ComponentsPlugin},
+    game_scene::{CustomProps, SceneComponents},
+    levels::data::{ComponentDescription, LevelDescription},
+};
+
+/// Entity the game spawns for a glTF node or mesh primitive.
+struct Node {
+    name: String,
+    /// Component attached with the `component` extra.
+    component: Option<ComponentDescription>,
+    mesh: bool,
+    collider: bool,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn any(&self, f: &impl Fn(&Node) -> bool) -> bool {
+        f(self) || self.children.iter().any(|child| child.any(f))
+    }
+
+    fn visit<'a>(&'a self, f: &mut impl FnMut(&'a Node)) {
+        f(self);
+        for child in &self.children {
+            child.visit(f);
+        }
+    }
+}
+
+#[derive(PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+pub struct Issue {
+    pub severity: Severity,
+    pub scene: usize,
+    pub node: String,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{severity}: scene {}: '{}' {}",
+            self.scene, self.node, self.message
+        )
+    }
+}
+
+/// `path` if it exists, otherwise `path` inside the assets folder.
+pub fn resolve(path: &Path) -> PathBuf {
+    if path.exists() {
+        path.to_path_buf()
+    } else {
+        FileAssetReader::get_base_path().join("assets").join(path)
+    }
+}
+
+/// The components the game registers, to check their settings like the game reads them.
+pub fn scene_components() -> SceneComponents {
+    let mut app = App::new();
+    app.add_plugins(ComponentsPlugin);
+    app.world
+        .remove_resource::<SceneComponents>()
+        .unwrap_or_default()
+}
+
+/// Checks every scene of a glTF file. Components come from the `component` extras and the level
+/// descriptions that use the scene, or are guessed from node names like `gate.004` for scenes
+/// without a description. The settings of the components are checked against `registered`,
+/// except for guessed components, which have none.
+pub fn check(
+    path: &Path,
+    levels: &BTreeMap<usize, LevelDescription>,
+    registered: &SceneComponents,
+) -> Result<Vec<Issue>> {
+    let gltf =
+        gltf::Gltf::open(resolve(path)).context(format!("Failed to open '{}'", path.display()))?;
+    let file = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or_default();
+    let animations = gltf
+        .animations()
+        .filter_map(|animation| animation.name())
+        .collect::<HashSet<_>>();
+    let clips = animations
+        .iter()
+        .map(|name| (name.to_string(), Handle::default()))
+        .collect::<HashMap<_, _>>();
+    // The components are inserted into a scratch world that is never updated.
+    let world = World::new();
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, &world);
+
+    let mut issues = vec![];
+    for scene in gltf.scenes() {
+        let mut report = |node: &str, severity, message: String| {
+            issues.push(Issue {
+                severity,
+                scene: scene.index(),
+                node: node.to_string(),
+                message,
+            })
+        };
+
+        let roots = scene
+            .nodes()
+            .map(|node| load(node, Mat4::IDENTITY, &CustomProps::default(), &mut report))
+            .collect::<Vec<_>>();
+
+        let described = levels
+            .values()
+            .filter(|level| level.scene == file && level.index as usize == scene.index())
+            .flat_map(|level| level.components.iter())
+            .map(|(name, component)| (name.clone(), component.clone()))
+            .collect::<BTreeMap<_, _>>();
+        let guessing = described.is_empty();
+        let mut components = if guessing {
+            let mut guessed = BTreeMap::new();
+            for root in &roots {
+                root.visit(&mut |node| {
+                    if let Some(component) = guess(&node.name) {
+                        guessed.insert(node.name.clone(), component);
+                    }
+                });
+            }
+            guessed
+        } else {
+            described
+        };
+        let mut from_extras = HashSet::new();
+        for root in &roots {
+            root.visit(&mut |node| {
+                if let Some(component) = &node.component {
+                    components.insert(node.name.clone(), component.clone());
+                    from_extras.insert(node.name.clone());
+                }
+            });
+        }
+
+        for (name, component) in &components {
+            let mut expected = vec![];
+            if !guessing || from_extras.contains(name) {
+                if let Err(e) = registered.insert(
+                    &mut commands.spawn_empty(),
+                    &component.kind,
+                    component.extras.clone(),
+                    &clips,
+                ) {
+                    report(name, Severity::Error, format!("expected {e}"));
+                    expected.push(e);
+                }
+            }
+
+            let mut nodes = vec![];
+            for root in &roots {
+                root.visit(&mut |node| {
+                    if node.name == *name {
+                        nodes.push(node);
+                    }
+                });
+            }
+            if nodes.is_empty() {
+                report(
+                    name,
+                    Severity::Error,
+                    format!("expected to exist for its \"{}\" component", component.kind),
+                );
+            }
+            for node in nodes {
+                let Some(missing) = missing(node, component, &animations) else {
+                    if !expected.is_empty() {
+                        continue;
+                    }
+                    report(
+                        name,
+                        Severity::Warning,
+                        format!(
+                            "has a component \"{}\" that cannot be checked",
+                            component.kind
+                        ),
+                    );
+                    continue;
+                };
+                for part in missing {
+                    if !expected.contains(&part) {
+                        report(name, Severity::Error, format!("expected {part}"));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Builds the entities the game spawns for `node`, checking the extras on the way.
+fn load(
+    node: gltf::Node,
+    parent: Mat4,
+    inherited: &CustomProps,
+    report: &mut impl FnMut(&str, Severity, String),
+) -> Node {
+    let name = node
+        .name()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| format!("GltfNode{}", node.index()));
+    let transform = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+    let (props, component) = parse_extras(&name, node.extras(), report);
+    let props = props.inherit(inherited);
+
+    let mut children = vec![];
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            // Named like the entities of `bevy_gltf`.
+            let name = match mesh.primitives().len() {
+                1 => mesh.name().unwrap_or("Mesh").to_string(),
+                _ => format!("{}.{}", mesh.name().unwrap_or("Mesh"), primitive.index()),
+            };
+            let props = parse_extras(&name, primitive.extras(), report)
+                .0
+                .inherit(&props);
+            let bounds = primitive.bounding_box();
+            let p1 = transform.transform_point3(bounds.min.into());
+            let p2 = transform.transform_point3(bounds.max.into());
+            children.push(Node {
+                component: None,
+                collider: props.has_collider(p1.z.min(p2.z), p1.z.max(p2.z)),
+                name,
+                mesh: true,
+                children: vec![],
+            });
+        }
+    }
+    children.extend(
+        node.children()
+            .map(|child| load(child, transform, &props, report)),
+    );
+
+    Node {
+        name,
+        component,
+        mesh: false,
+        collider: false,
+        children,
+    }
+}
+
+fn parse_extras(
+    name: &str,
+    extras: &gltf::json::Extras,
+    report: &mut impl FnMut(&str, Severity, String),
+) -> (CustomProps, Option<ComponentDescription>) {
+    let Some(extras) = extras else {
+        return Default::default();
+    };
+    let map = match serde_json::from_str::<Map<String, Value>>(extras.get()) {
+        Ok(map) => map,
+        Err(e) => {
+            report(name, Severity::Error, format!("has invalid extras: {e}"));
+            return Default::default();
+        }
+    };
+
+    let props = serde_json::from_value(Value::Object(map.clone())).unwrap_or_else(|e| {
+        report(name, Severity::Error, format!("has invalid extras: {e}"));
+        CustomProps::default()
+    });
+
+    // The other extras of a component node are the settings of the component.
+    let Some(component) = map.get("component") else {
+        for key in map.keys() {
+            if !CustomProps::KEYS.contains(&key.as_str()) {
+                report(
+                    name,
+                    Severity::Warning,
+                    format!("has unknown extra '{key}'"),
+                );
+            }
+        }
+        return (props, None);
+    };
+    let Value::String(kind) = component else {
+        report(
+            name,
+            Severity::Error,
+            format!("has a component name {component} that is not a string"),
+        );
+        return (props, None);
+    };
+    let kind = kind.clone();
+    let extras = map
+        .into_iter()
+        .filter(|(key, _)| {
+            !CustomProps::KEYS.contains(&key.as_str()) && key != "id" && key != "component"
+        })
+        .collect();
+    (props, Some(ComponentDescription { kind, extras }))
+}
+
+/// Component of a node named like the level designers name them, e.g. `code.002`.
+fn guess(name: &str) -> Option<ComponentDescription> {
+    let (prefix, number) = name.split_once('.')?;
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (kind, start) = match prefix {
+        "air_station" | "code" | "current" | "fan" | "gate" | "pressure_plate" | "switch" => {
+            (prefix, None)
+        }
+        "camera" => ("security_camera", None),
+        "socket_start" => ("socket", Some(true)),
+        "socket_end" => ("socket", Some(false)),
+        _ => return None,
+    };
+    let mut extras = Map::new();
+    if let Some(start) = start {
+        extras.insert("start".to_string(), start.into());
+    }
+    Some(ComponentDescription {
+        kind: kind.to_string(),
+        extras,
+    })
+}
+
+/// Parts the `init` system of `component` looks for below `node` that are not there, or `None`
+/// for components registered by other plugins.
+fn missing(
+    node: &Node,
+    component: &ComponentDescription,
+    animations: &HashSet<&str>,
+) -> Option<Vec<String>> {
+    let collider = |part: &str| node.any(&|n| n.name.contains(part) && n.collider);
+    let mesh = |part: &str| node.any(&|n| n.name.contains(part) && n.mesh);
+    let mut missing = vec![];
+    let mut require = |found: bool, expected: String| {
+        if !found {
+            missing.push(expected);
+        }
+    };
+
+    match component.kind.as_str() {
+        "air_station" => {
+            require(collider("sensor"), "a \"sensor\" collider".into());
+        }
+        "code" => {
+            let mut segments = [[false; 7]; 4];
+            let mut buttons = [false; 10];
+            node.visit(&mut |n| {
+                if let Some((digit, segment)) = code::segment_index(&n.name) {
+                    segments[digit][segment] = true;
+                }
+                if let Some(number) = code::button_number(&n.name) {
+                    buttons[number as usize] = true;
+                }
+            });
+            missing.extend(code::missing_parts(
+                mesh("screen"),
+                collider("sensor"),
+                &segments,
+                &buttons,
+            ));
+        }
+        "current" => {
+            require(node.any(&|n| n.collider), "a collider".into());
+        }
+        "fan" => {
+            require(collider("pusher"), "a \"pusher\" collider".into());
+            require(
+                node.any(&|n| n.name.contains("fan.")),
+                "a \"fan.\" model".into(),
+            );
+        }
+        "gate" => {
+            require(collider("physics"), "a \"physics\" collider".into());
+            require(
+                animations.contains("gate_open"),
+                "an animation \"gate_open\"".into(),
+            );
+        }
+        "pressure_plate" => {
+            require(collider("sensor"), "a \"sensor\" collider".into());
+        }
+        "security_camera" => {
+            require(mesh("cone"), "a \"cone\" mesh".into());
+            require(collider("sensor"), "a \"sensor\" collider".into());
+        }
+        "socket" => {
+            require(collider("sensor"), "a \"sensor\" collider".into());
+        }
+        "switch" => {
+            require(collider("sensor"), "a \"sensor\" collider".into());
+            require(
+                animations.contains("switch_pull"),
+                "an animation \"switch_pull\"".into(),
+            );
+        }
+        _ => return None,
+    }
+    Some(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(levels: &BTreeMap<usize, LevelDescription>) -> Vec<String> {
+        check(Path::new("lvl0.glb"), levels, &scene_components())
+            .expect("Failed to check lvl0.glb")
+            .into_iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| format!("'{}' {}", issue.node, issue.message))
+            .collect()
+    }
+
+    #[test]
+    fn lvl0_is_valid() {
+        assert_eq!(errors(&BTreeMap::new()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn broken_description() {
+        let level = serde_json::from_value(serde_json::json!({
+            "scene": "lvl0.glb",
+            "index": 0,
+            "components": {
+                "Cube": { "type": "code", "secret": 12345 },
+                "Plane": { "type": "pressure_plate", "mode": "sideways" },
+                "Light": { "type": "teleporter" },
+                "gate.9": { "type": "gate" }
+            }
+        }))
+        .unwrap();
+        let errors = errors(&BTreeMap::from([(1, level)]));
+
+        let has = |node: &str, message: &str| {
+            errors
+                .iter()
+                .any(|error| error.starts_with(&format!("'{node}' expected {message}")))
+        };
+        assert!(has("Cube", "a secret of at most 4 digits"), "{errors:#?}");
+        assert!(has("Cube", "a \"screen\" mesh"), "{errors:#?}");
+        assert!(
+            has("Plane", "valid \"pressure_plate\" extras"),
+            "{errors:#?}"
+        );
+        assert!(has("Light", "a registered component"), "{errors:#?}");
+        assert!(has("gate.9", "to exist"), "{errors:#?}");
+        assert!(has("gate.9", "an animation \"gate_open\""), "{errors:#?}");
+        assert_eq!(
+            errors
+                .iter()
+                .filter(|error| error.contains("gate_open"))
+                .count(),
+            1,
+            "{errors:#?}"
+        );
+    }
+}
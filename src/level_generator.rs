@@ -1,8 +1,8 @@
 use std::collections::{HashMap, HashSet};
 
-use rand::seq::SliceRandom;
+use rand::{seq::SliceRandom, Rng};
 
-pub fn generate() -> String {
+pub fn generate(rng: &mut impl Rng) -> String {
     let nodes = ["wall", "fan", "gate", "camera"];
     let after = HashMap::from([
         ("input", vec!["tip"]),
@@ -17,7 +17,6 @@ pub fn generate() -> String {
     let mut res = vec![];
 
     let steps = 4;
-    let mut rng = rand::thread_rng();
     let mut cur_node = None;
     let mut i = 0;
     loop {
@@ -31,7 +30,7 @@ pub fn generate() -> String {
                     res.push("|");
                 }
                 i += 1;
-                nodes.choose(&mut rng)
+                nodes.choose(rng)
             }
             node => node,
         }
@@ -42,7 +41,7 @@ pub fn generate() -> String {
             after_done.remove(node);
         }
 
-        cur_node = match after.get(node).and_then(|after| after.choose(&mut rng)) {
+        cur_node = match after.get(node).and_then(|after| after.choose(rng)) {
             Some(after) => match after_done.get(after) {
                 Some(_) => None,
                 None => Some(after),
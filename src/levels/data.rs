@@ -0,0 +1,193 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    checkpoint::{ActiveCheckpoint, CheckpointSet, RespawnAtCheckpoint},
+    components::loading::Loading,
+    game_scene::{GameScene, GameSceneData, SceneComponents},
+    oxygen::Oxygen,
+    player::Player,
+    simulation::SimulationSet,
+    utils::reduce_to_root,
+    validation::SceneWiringError,
+    wiring::{WiringDescription, WiringGraph, WiringSet},
+    GameState, Restart,
+};
+
+use super::{LevelCompleted, LoadLevel};
+
+/// Component of a scene node, attached like one named in the node's `component` extra.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ComponentDescription {
+    /// Name the component is registered under with `add_scene_component`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Settings of the component, the same as its extras would be.
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
+}
+
+#[derive(Resource, Deserialize, Clone, Debug)]
+pub struct LevelDescription {
+    pub scene: String,
+    pub index: u32,
+    #[serde(default)]
+    pub components: HashMap<String, ComponentDescription>,
+    #[serde(default)]
+    pub wiring: WiringDescription,
+    /// Level entered once the `level:complete` input turns on.
+    #[serde(default)]
+    pub next: Option<usize>,
+    /// Seconds of air in a full tank. Levels without it have unlimited air.
+    #[serde(default)]
+    pub oxygen: Option<f32>,
+}
+
+/// Reads the level manifest (level number -> description file) from the assets folder.
+pub fn load_manifest(path: &str) -> Result<BTreeMap<usize, LevelDescription>> {
+    let assets = FileAssetReader::get_base_path().join("assets");
+    let read = |path: &Path| {
+        fs::read_to_string(assets.join(path))
+            .context(format!("Failed to read '{}'", path.display()))
+    };
+
+    let manifest: BTreeMap<usize, String> = serde_json::from_str(&read(Path::new(path))?)
+        .context(format!("Failed to parse level manifest '{path}'"))?;
+
+    manifest
+        .into_iter()
+        .map(|(level, file)| {
+            let description = serde_json::from_str(&read(Path::new(&file))?)
+                .context(format!("Failed to parse level description '{file}'"))?;
+            Ok((level, description))
+        })
+        .collect()
+}
+
+#[derive(Resource)]
+pub struct DataLevel {
+    scene_data: GameSceneData,
+}
+
+impl GameScene for DataLevel {
+    fn from_scene_data(data: GameSceneData) -> Self {
+        Self { scene_data: data }
+    }
+}
+
+pub fn build_systems(app: &mut App) {
+    app.add_systems(Update, ready.run_if(resource_added::<DataLevel>()))
+        .add_systems(
+            FixedUpdate,
+            process_level
+                .in_set(SimulationSet::Logic)
+                .after(WiringSet::Evaluate)
+                .before(CheckpointSet::Restore)
+                .run_if(resource_exists::<Player>())
+                .run_if(resource_exists::<WiringGraph>())
+                .run_if(not(any_with_component::<Loading>())),
+        );
+}
+
+pub fn build(state: GameState, description: LevelDescription, app: &mut App) {
+    app.add_systems(OnEnter(state.clone()), move |mut commands: Commands| {
+        commands.insert_resource(LoadLevel::new::<DataLevel>(
+            &description.scene,
+            description.index,
+        ));
+        if let Some(capacity) = description.oxygen {
+            commands.insert_resource(Oxygen::new(capacity));
+        }
+        commands.insert_resource(description.clone());
+    });
+    app.add_systems(OnExit(state), cleanup);
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<DataLevel>();
+    commands.remove_resource::<LevelDescription>();
+    commands.remove_resource::<WiringGraph>();
+    commands.remove_resource::<Oxygen>();
+}
+
+fn ready(
+    mut commands: Commands,
+    mut errors: EventWriter<SceneWiringError>,
+    level: Res<DataLevel>,
+    description: Res<LevelDescription>,
+    components: Res<SceneComponents>,
+    entities: Query<(Entity, &Name)>,
+    children: Query<&Parent>,
+    state: Res<State<GameState>>,
+) {
+    let root = level.scene_data.root;
+    let mut found = HashSet::new();
+    for (entity, name) in entities.iter() {
+        if !reduce_to_root(&children, entity, false, |f, r| f || (r == root)) {
+            continue;
+        }
+        if let Some(component) = description.components.get(name.as_str()) {
+            found.insert(name.as_str());
+            if let Err(expected) = components.insert(
+                &mut commands.entity(entity),
+                &component.kind,
+                component.extras.clone(),
+                &level.scene_data.animations,
+            ) {
+                errors.send(SceneWiringError::new(state.get(), name, expected));
+            }
+        }
+    }
+    for (name, component) in description.components.iter() {
+        if !found.contains(name.as_str()) {
+            errors.send(SceneWiringError::new(
+                state.get(),
+                name,
+                format!(
+                    "to exist in '{}' for its \"{}\" component",
+                    description.scene, component.kind
+                ),
+            ));
+        }
+    }
+
+    commands.insert_resource(WiringGraph::new(description.wiring.clone()));
+}
+
+fn process_level(
+    mut commands: Commands,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut completed: EventWriter<LevelCompleted>,
+    mut respawn: EventWriter<RespawnAtCheckpoint>,
+    checkpoint: Option<Res<ActiveCheckpoint>>,
+    state: Res<State<GameState>>,
+    description: Res<LevelDescription>,
+    graph: Res<WiringGraph>,
+) {
+    // Both fire once when the input turns on, not on every step it stays on.
+    if graph.rose("level", "restart") {
+        if checkpoint.is_some() {
+            respawn.send(RespawnAtCheckpoint);
+        } else {
+            commands.insert_resource(Restart(state.get().clone()));
+            game_state.set(GameState::Restart);
+        }
+    }
+
+    if graph.rose("level", "complete") {
+        if let GameState::Level(level) = state.get() {
+            completed.send(LevelCompleted { level: *level });
+        }
+        if let Some(next) = description.next {
+            game_state.set(GameState::Level(next));
+        }
+    }
+}
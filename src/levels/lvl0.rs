@@ -4,9 +4,18 @@ use bevy::{
     prelude::*,
     render::view::RenderLayers,
 };
+use bevy_rapier2d::geometry::Collider;
 
 use crate::{
-    components::loading::Loading, game_scene::{GameScene, GameSceneData}, handle_errors, materials::paint_material::PaintMaterial, player::{Direction, Player, PlayerCollision}, utils::reduce_to_root, GameState
+    components::{current::CurrentVolume, loading::Loading},
+    game_scene::{GameScene, GameSceneData},
+    handle_errors,
+    interaction::{Interactable, Interacted},
+    materials::paint_material::PaintMaterial,
+    player::{Direction, Player},
+    simulation::SimulationSet,
+    utils::reduce_to_root,
+    GameState,
 };
 
 use super::{GameLevel, LoadLevel};
@@ -15,7 +24,7 @@ use super::{GameLevel, LoadLevel};
 pub struct Level0 {
     scene_data: GameSceneData,
     lever1_clicked: bool,
-    pusher1_active: bool,
+    pub pusher1_active: bool,
 }
 
 impl GameScene for Level0 {
@@ -36,16 +45,24 @@ impl GameLevel for Level0 {
             Update,
             (
                 ready.run_if(resource_added::<Level0>()),
-                (
-                    process_sensors.pipe(handle_errors),
-                    process_animations.pipe(handle_errors),
-                )
+                process_animations
+                    .pipe(handle_errors)
                     .run_if(in_state(state.clone()))
                     .run_if(resource_exists::<Level0>())
                     .run_if(resource_exists::<Player>())
                     .run_if(not(any_with_component::<Loading>())),
             ),
         );
+        app.add_systems(
+            FixedUpdate,
+            process_sensors
+                .pipe(handle_errors)
+                .in_set(SimulationSet::Interact)
+                .run_if(in_state(state.clone()))
+                .run_if(resource_exists::<Level0>())
+                .run_if(resource_exists::<Player>())
+                .run_if(not(any_with_component::<Loading>())),
+        );
     }
 }
 
@@ -63,9 +80,28 @@ fn ready(
     materials: ResMut<Assets<StandardMaterial>>,
     level: Res<Level0>,
     entities: Query<(Entity, &Name, &Handle<StandardMaterial>)>,
+    sensors: Query<(Entity, &Name), With<Collider>>,
     children: Query<&Parent>,
 ) {
     let root = level.scene_data.root;
+    for (entity, name) in sensors.iter() {
+        if !reduce_to_root(&children, entity, false, |f, r| f || (r == root)) {
+            continue;
+        }
+        match name.as_str() {
+            "lever1_sensor" => {
+                commands
+                    .entity(entity)
+                    .insert(Interactable::new("pull the lever"));
+            }
+            "pusher1" => {
+                commands
+                    .entity(entity)
+                    .insert(CurrentVolume::new(entity, Vec2::Y, 15.0));
+            }
+            _ => {}
+        }
+    }
     for (entity, name, mat) in entities.iter() {
         if !reduce_to_root(&children, entity, false, |f, r| f || (r == root)) {
             continue;
@@ -88,26 +124,26 @@ fn ready(
 }
 
 fn process_sensors(
-    names: Query<&Name>,
-    collisions: Query<&PlayerCollision>,
+    mut currents: Query<(&Name, &mut CurrentVolume)>,
+    mut interactables: Query<(Entity, &Name, &mut Interactable)>,
+    mut interacted: EventReader<Interacted>,
     mut level: ResMut<Level0>,
-    mut player: ResMut<Player>,
+    player: Res<Player>,
 ) -> Result<()> {
-    player.push_vec = Vec2::ZERO;
+    let interacted = interacted.read().map(|i| i.entity).collect::<Vec<_>>();
+    for (entity, name, mut interactable) in interactables.iter_mut() {
+        if name.as_str() != "lever1_sensor" {
+            continue;
+        }
+        if interactable.enabled && interacted.contains(&entity) {
+            level.pusher1_active = false;
+        }
+        interactable.enabled = level.pusher1_active && player.direction == Direction::Left;
+    }
 
-    for collision in collisions.iter() {
-        match names.get(collision.other).map(|n| n.as_str()) {
-            Ok("pusher1") => {
-                if level.pusher1_active {
-                    player.push_vec.y += 15.0
-                }
-            }
-            Ok("lever1_sensor") => {
-                if player.direction == Direction::Left && player.is_action {
-                    level.pusher1_active = false;
-                }
-            }
-            _ => {}
+    for (name, mut current) in currents.iter_mut() {
+        if name.as_str() == "pusher1" {
+            current.active = level.pusher1_active;
         }
     }
 
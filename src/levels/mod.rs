@@ -1,4 +1,9 @@
-use std::{collections::HashMap, f32::consts::FRAC_PI_3};
+use std::{
+    collections::{BTreeMap, HashMap},
+    f32::consts::FRAC_PI_3,
+};
+
+use anyhow::Result;
 
 use bevy::{
     core_pipeline::{
@@ -10,24 +15,31 @@ use bevy::{
     render::view::RenderLayers,
 };
 
+use self::data::LevelDescription;
 use crate::{
     game_scene::{GameScene, LoadGameScene},
     player::{LoadPlayer, Player, PlayerRoot},
     GameState, Restart,
 };
 
+pub mod data;
 pub mod lvl0;
-pub mod lvl1;
-pub mod lvl2;
-pub mod lvl3;
-pub mod lvl4;
 
 pub trait GameLevel {
     fn build(state: GameState, app: &mut App);
 }
 
 #[derive(Resource)]
-pub struct LevelRoot(Entity);
+pub struct LevelRoot(pub Entity);
+
+#[derive(Event)]
+pub struct LevelCompleted {
+    pub level: usize,
+}
+
+/// Numbers of all registered levels in ascending order.
+#[derive(Resource)]
+pub struct LevelList(pub Vec<usize>);
 
 #[derive(Resource)]
 pub struct LoadLevel {
@@ -58,6 +70,7 @@ impl LoadLevel {
 #[derive(Default)]
 pub struct LevelPlugin {
     levels: HashMap<GameState, Box<dyn Fn(&mut App) + Send + Sync>>,
+    data_levels: BTreeMap<usize, LevelDescription>,
 }
 
 impl LevelPlugin {
@@ -71,15 +84,49 @@ impl LevelPlugin {
         );
         self
     }
+
+    pub fn with_manifest(mut self, path: &str) -> Result<Self> {
+        self.data_levels.extend(data::load_manifest(path)?);
+        Ok(self)
+    }
+
+    pub fn with_description(mut self, level: usize, description: LevelDescription) -> Self {
+        self.data_levels.insert(level, description);
+        self
+    }
+
+    pub fn contains(&self, level: usize) -> bool {
+        self.data_levels.contains_key(&level) || self.levels.contains_key(&GameState::Level(level))
+    }
 }
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
+        app.add_state::<GameState>();
+        app.add_event::<LevelCompleted>();
         app.add_systems(Update, load.run_if(resource_exists::<LoadLevel>()));
         for (state, level) in &self.levels {
             app.add_systems(OnExit(state.clone()), cleanup);
             level(app);
         }
+        for (level, description) in &self.data_levels {
+            let state = GameState::Level(*level);
+            app.add_systems(OnExit(state.clone()), cleanup);
+            data::build(state, description.clone(), app);
+        }
+        data::build_systems(app);
+
+        let mut levels = self
+            .levels
+            .keys()
+            .filter_map(|state| match state {
+                GameState::Level(level) => Some(*level),
+                _ => None,
+            })
+            .chain(self.data_levels.keys().copied())
+            .collect::<Vec<_>>();
+        levels.sort();
+        app.insert_resource(LevelList(levels));
         app.add_systems(OnEnter(GameState::Restart), restart);
     }
 }
@@ -134,6 +181,9 @@ fn spawn_camera(commands: &mut Commands, order: u8, parent: Entity) {
                 ..Default::default()
             },
             RenderLayers::layer(order),
+            UiCameraConfig {
+                show_ui: order == 1,
+            },
             //ShadowFilteringMethod::Castano13,
             BloomSettings::default(),
             FogSettings {
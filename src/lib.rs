@@ -0,0 +1,109 @@
+use anyhow::Result;
+use bevy::{app::PluginGroupBuilder, pbr::ExtendedMaterial, prelude::*};
+use bevy_mod_raycast::DefaultRaycastingPlugin;
+use bevy_rapier2d::prelude::*;
+use camera::CameraPlugin;
+use checkpoint::CheckpointPlugin;
+use components::ComponentsPlugin;
+use game_scene::GameScenePlugin;
+use input::InputMapPlugin;
+use interaction::InteractionPlugin;
+use levels::{lvl0::Level0, LevelPlugin};
+use materials::{beam_material::BeamMaterial, paint_material::PaintMaterial};
+use menu::MenuPlugin;
+use oxygen::OxygenPlugin;
+use player::PlayerPlugin;
+use sensor::SensorPlugin;
+use simulation::SimulationPlugin;
+use validation::ValidationPlugin;
+use wiring::WiringPlugin;
+
+pub mod mips;
+
+pub mod camera;
+pub mod checkpoint;
+pub mod cli;
+pub mod components;
+pub mod game_scene;
+pub mod headless;
+pub mod input;
+pub mod interaction;
+pub mod level_check;
+pub mod levels;
+pub mod materials;
+pub mod menu;
+pub mod oxygen;
+pub mod player;
+pub mod replay;
+pub mod save;
+pub mod sensor;
+pub mod simulation;
+pub mod utils;
+pub mod validation;
+pub mod wiring;
+
+pub mod level_generator;
+
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, States)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    LevelSelect,
+    Settings,
+    Restart,
+    Level(usize),
+}
+
+#[derive(Resource)]
+pub struct Restart(GameState);
+
+pub fn handle_errors(In(result): In<Result<()>>) {
+    if let Err(e) = result {
+        error!("System early returned: {e:#}");
+    }
+}
+
+/// Gameplay, physics, scenes and materials of the game. Window, rendering and input come
+/// from `DefaultPlugins` or `headless_plugins`; the menus also expect a `SaveGame` resource.
+pub struct GamePlugins {
+    pub levels: LevelPlugin,
+}
+
+impl GamePlugins {
+    /// The game with level 0 and the levels of `levels.json`.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            levels: LevelPlugin::default()
+                .with_level::<Level0>(GameState::Level(0))
+                .with_manifest("levels.json")?,
+        })
+    }
+}
+
+impl PluginGroup for GamePlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(DefaultRaycastingPlugin)
+            .add(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
+            .add(SimulationPlugin)
+            .add(InputMapPlugin::default())
+            .add(MaterialPlugin::<
+                ExtendedMaterial<StandardMaterial, PaintMaterial>,
+            >::default())
+            .add(MaterialPlugin::<
+                ExtendedMaterial<StandardMaterial, BeamMaterial>,
+            >::default())
+            .add(ComponentsPlugin)
+            .add(WiringPlugin)
+            .add(GameScenePlugin)
+            .add(CameraPlugin)
+            .add(PlayerPlugin)
+            .add(SensorPlugin)
+            .add(InteractionPlugin)
+            .add(OxygenPlugin)
+            .add(MenuPlugin)
+            .add(CheckpointPlugin)
+            .add(ValidationPlugin)
+            .add(self.levels)
+    }
+}
@@ -1,100 +1,98 @@
-use anyhow::Result;
-use bevy::{
-    log::{self, LogPlugin},
-    pbr::ExtendedMaterial,
-    prelude::*,
-};
-use bevy_hanabi::HanabiPlugin;
-use bevy_inspector_egui::{quick::WorldInspectorPlugin, DefaultInspectorConfigPlugin};
-use bevy_mod_raycast::DefaultRaycastingPlugin;
+use anyhow::{ensure, Result};
+use bevy::{log::LogPlugin, prelude::*};
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rapier2d::prelude::*;
-use camera::CameraPlugin;
-use components::{
-    code::CodePlugin, fan::FanPlugin, gate::GatePlugin, security_camera::SecurityCameraPlugin,
-    socket::SocketPlugin, switch::SwitchPlugin,
+use clap::Parser;
+use twg_2024_1::{
+    cli::Cli,
+    headless::{headless_plugins, HeadlessPlugin},
+    mips::{generate_mipmaps, MipmapGeneratorPlugin},
+    replay::{Recording, ReplayPlugin},
+    save::{SaveGame, SavePlugin},
+    utils::GameRng,
+    GamePlugins, GameState,
 };
-use game_scene::GameScenePlugin;
-use levels::{lvl0::Level0, lvl1::Level1, lvl2::Level2, lvl3::Level3, lvl4::Level4, LevelPlugin};
-use materials::{beam_material::BeamMaterial, paint_material::PaintMaterial};
-use mips::{generate_mipmaps, MipmapGeneratorPlugin};
-use player::PlayerPlugin;
-
-mod mips;
-
-mod camera;
-mod components;
-mod game_scene;
-mod levels;
-mod materials;
-mod player;
-mod utils;
 
-mod level_generator;
+fn main() -> Result<()> {
+    build_app(Cli::parse())?.run();
 
-#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, States)]
-enum GameState {
-    Restart,
-    Level0,
-    Level1,
-    #[default]
-    Level2,
-    Level3,
-    Level4,
+    Ok(())
 }
 
-#[derive(Resource)]
-pub struct Restart(GameState);
+fn build_app(mut cli: Cli) -> Result<App> {
+    let replay = match cli.replay {
+        Some(ref path) => Some(Recording::load(path)?),
+        None => None,
+    };
+    if let Some(ref recording) = replay {
+        cli.level = Some(recording.level);
+        cli.seed = Some(recording.seed);
+    }
+    if cli.record.is_some() && cli.seed.is_none() {
+        cli.seed = Some(rand::random());
+    }
+
+    let game = GamePlugins::new()?;
+    if let Some(level) = cli.level {
+        ensure!(game.levels.contains(level), "Level {level} does not exist");
+    }
+    ensure!(
+        !cli.headless || cli.level.is_some(),
+        "Headless mode needs --level or --replay"
+    );
 
-pub(crate) fn handle_errors(In(result): In<Result<()>>) {
-    if let Err(e) = result {
-        eprintln!("System early returned: {}", e);
+    let mut window = bevy_mod_raycast::low_latency_window_plugin();
+    if let Some(ref mut primary) = window.primary_window {
+        cli.configure_window(primary);
     }
-}
 
-fn main() {
-    App::new()
-        .insert_resource(RapierConfiguration {
-            gravity: Vec2::ZERO,
-            ..Default::default()
-        })
+    let mut app = App::new();
+    app.insert_resource(GameRng(cli.rng()))
         .insert_resource(AmbientLight {
             color: Color::WHITE,
             brightness: 0.0,
-        })
-        .add_plugins((
-            DefaultPlugins
-                .set(bevy_mod_raycast::low_latency_window_plugin())
-                .set(LogPlugin {
-                    level: log::Level::ERROR,
-                    ..Default::default()
-                }),
-            DefaultRaycastingPlugin,
-            MipmapGeneratorPlugin,
-            //HanabiPlugin,
-            RapierPhysicsPlugin::<NoUserData>::default(),
-            //RapierDebugRenderPlugin::default(),
-            //WorldInspectorPlugin::new(),
+        });
+
+    let log = LogPlugin {
+        level: cli.log_level,
+        ..Default::default()
+    };
+    if cli.headless {
+        app.add_plugins((
+            headless_plugins().set(log),
+            HeadlessPlugin { frames: cli.frames },
         ))
-        .add_systems(Update, generate_mipmaps::<StandardMaterial>)
-        .add_plugins((
-            MaterialPlugin::<ExtendedMaterial<StandardMaterial, PaintMaterial>>::default(),
-            MaterialPlugin::<ExtendedMaterial<StandardMaterial, BeamMaterial>>::default(),
-            SecurityCameraPlugin,
-            SwitchPlugin,
-            GatePlugin,
-            CodePlugin,
-            SocketPlugin,
-            FanPlugin,
-            GameScenePlugin,
-            CameraPlugin,
-            PlayerPlugin,
-            LevelPlugin::default()
-                .with_level::<Level0>(GameState::Level0)
-                .with_level::<Level1>(GameState::Level1)
-                .with_level::<Level2>(GameState::Level2)
-                .with_level::<Level3>(GameState::Level3)
-                .with_level::<Level4>(GameState::Level4),
+        .init_resource::<SaveGame>();
+    } else {
+        app.add_plugins((
+            DefaultPlugins.set(window).set(log),
+            MipmapGeneratorPlugin,
+            SavePlugin,
         ))
-        .add_state::<GameState>()
-        .run();
+        .add_systems(Update, generate_mipmaps::<StandardMaterial>);
+    }
+
+    app.add_plugins(game);
+
+    if cli.inspector {
+        app.add_plugins(WorldInspectorPlugin::new());
+    }
+    if cli.physics_debug {
+        app.add_plugins(RapierDebugRenderPlugin::default());
+    }
+    if let Some(level) = cli.level {
+        app.insert_resource(State::new(GameState::Level(level)));
+    }
+    if let Some(recording) = replay {
+        app.add_plugins(ReplayPlugin::Replay(recording));
+    } else if let (Some(path), Some(level), Some(seed)) = (&cli.record, cli.level, cli.seed) {
+        app.add_plugins(ReplayPlugin::Record(
+            path.clone(),
+            Recording::new(level, seed),
+        ));
+    }
+
+    app.insert_resource(cli);
+
+    Ok(app)
 }
@@ -0,0 +1,409 @@
+use bevy::{app::AppExit, prelude::*};
+use bevy_rapier2d::plugin::RapierConfiguration;
+
+use crate::{
+    input::{Action, ActionState, Binding, InputMap},
+    levels::LevelList,
+    player::Player,
+    save::SaveGame,
+    GameState, Restart,
+};
+
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq, States)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component)]
+struct PauseRoot;
+
+/// Action waiting for a key or button in the settings menu.
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+/// Button selected with the move actions, for playing without a mouse.
+#[derive(Resource, Default)]
+struct MenuFocus(Option<Entity>);
+
+#[derive(Component, Clone)]
+enum MenuButton {
+    Continue,
+    LevelSelect,
+    Settings,
+    Quit,
+    Level(usize),
+    Back,
+    Rebind(Action),
+    Fullscreen,
+    Resume,
+    RestartLevel,
+    QuitToMenu,
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<PauseState>()
+            .init_resource::<Rebinding>()
+            .init_resource::<MenuFocus>()
+            .add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), despawn::<MenuRoot>)
+            .add_systems(OnEnter(GameState::LevelSelect), spawn_level_select)
+            .add_systems(OnExit(GameState::LevelSelect), despawn::<MenuRoot>)
+            .add_systems(OnEnter(GameState::Settings), spawn_settings)
+            .add_systems(
+                OnExit(GameState::Settings),
+                (despawn::<MenuRoot>, stop_rebinding),
+            )
+            .add_systems(OnEnter(PauseState::Paused), (pause, spawn_pause_menu))
+            .add_systems(OnExit(PauseState::Paused), (resume, despawn::<PauseRoot>))
+            .add_systems(
+                Update,
+                (
+                    toggle_pause.run_if(resource_exists::<Player>()),
+                    rebind.before(buttons),
+                    navigate.before(buttons),
+                    buttons,
+                    (despawn::<MenuRoot>, spawn_settings).chain().run_if(
+                        in_state(GameState::Settings).and_then(
+                            resource_changed::<InputMap>()
+                                .or_else(resource_changed::<Rebinding>())
+                                .or_else(resource_changed::<SaveGame>()),
+                        ),
+                    ),
+                ),
+            );
+    }
+}
+
+fn toggle_pause(
+    actions: Res<ActionState>,
+    state: Res<State<PauseState>>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+) {
+    if !actions.just_pressed(Action::Pause) {
+        return;
+    }
+    pause_state.set(match state.get() {
+        PauseState::Running => PauseState::Paused,
+        PauseState::Paused => PauseState::Running,
+    });
+}
+
+fn pause(
+    mut time: ResMut<Time<Virtual>>,
+    mut rapier: ResMut<RapierConfiguration>,
+    mut player: Option<ResMut<Player>>,
+) {
+    time.pause();
+    rapier.physics_pipeline_active = false;
+    if let Some(ref mut player) = player {
+        player.clear_input();
+    }
+}
+
+fn resume(mut time: ResMut<Time<Virtual>>, mut rapier: ResMut<RapierConfiguration>) {
+    time.unpause();
+    rapier.physics_pipeline_active = true;
+}
+
+/// Binds the first key or button pressed to the action being rebound. It replaces the
+/// bindings of the same kind, so a gamepad binding survives rebinding the keyboard.
+fn rebind(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut map: ResMut<InputMap>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    let binding = keyboard_input
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or(mouse_input
+            .get_just_pressed()
+            .next()
+            .map(|button| Binding::Mouse(*button)))
+        .or(gamepad_input
+            .get_just_pressed()
+            .next()
+            .map(|button| Binding::Gamepad(button.button_type)));
+    if let Some(binding) = binding {
+        let bindings = map.0.entry(action).or_default();
+        bindings.retain(|b| b.is_gamepad() != binding.is_gamepad());
+        bindings.push(binding);
+        rebinding.0 = None;
+    }
+}
+
+/// Moves the focus between menu buttons and presses the focused one on interact.
+fn navigate(
+    actions: Res<ActionState>,
+    rebinding: Res<Rebinding>,
+    mut focus: ResMut<MenuFocus>,
+    mut buttons: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut Interaction,
+            &mut BackgroundColor,
+        ),
+        With<MenuButton>,
+    >,
+) {
+    if rebinding.0.is_some() {
+        return;
+    }
+    let step = match (
+        actions.just_pressed(Action::MoveUp),
+        actions.just_pressed(Action::MoveDown),
+    ) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => 0,
+    };
+
+    let mut order = buttons
+        .iter()
+        .map(|(entity, transform, ..)| (entity, transform.translation().y))
+        .collect::<Vec<_>>();
+    order.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    let current = order
+        .iter()
+        .position(|(entity, _)| Some(*entity) == focus.0);
+
+    if step != 0 {
+        let next = match current {
+            Some(current) => (current as i32 + step).clamp(0, order.len() as i32 - 1) as usize,
+            None => 0,
+        };
+        focus.0 = order.get(next).map(|(entity, _)| *entity);
+        for (entity, _, _, mut color) in buttons.iter_mut() {
+            *color = if Some(entity) == focus.0 {
+                HOVERED
+            } else {
+                NORMAL
+            }
+            .into();
+        }
+    } else if current.is_some() && actions.just_pressed(Action::Interact) {
+        if let Ok((_, _, mut interaction, _)) = buttons.get_mut(focus.0.unwrap()) {
+            *interaction = Interaction::Pressed;
+        }
+    }
+}
+
+fn stop_rebinding(mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+}
+
+fn buttons(
+    mut commands: Commands,
+    mut rebinding: ResMut<Rebinding>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    mut exit: EventWriter<AppExit>,
+    mut buttons: Query<(&Interaction, &MenuButton, &mut BackgroundColor), Changed<Interaction>>,
+    state: Res<State<GameState>>,
+    mut save: ResMut<SaveGame>,
+    levels: Res<LevelList>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::Pressed => PRESSED,
+            Interaction::Hovered => HOVERED,
+            Interaction::None => NORMAL,
+        }
+        .into();
+
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            MenuButton::Continue => {
+                if let Some(level) = save.furthest.or(levels.0.first().copied()) {
+                    game_state.set(GameState::Level(level));
+                }
+            }
+            MenuButton::LevelSelect => game_state.set(GameState::LevelSelect),
+            MenuButton::Settings => game_state.set(GameState::Settings),
+            MenuButton::Quit => exit.send(AppExit),
+            MenuButton::Level(level) => game_state.set(GameState::Level(*level)),
+            MenuButton::Back => game_state.set(GameState::MainMenu),
+            MenuButton::Rebind(action) => rebinding.0 = Some(*action),
+            MenuButton::Fullscreen => save.settings.fullscreen ^= true,
+            MenuButton::Resume => pause_state.set(PauseState::Running),
+            MenuButton::RestartLevel => {
+                commands.insert_resource(Restart(state.get().clone()));
+                game_state.set(GameState::Restart);
+                pause_state.set(PauseState::Running);
+            }
+            MenuButton::QuitToMenu => {
+                game_state.set(GameState::MainMenu);
+                pause_state.set(PauseState::Running);
+            }
+        }
+    }
+}
+
+const NORMAL: Color = Color::rgba(0.0, 0.25, 0.3, 0.8);
+const HOVERED: Color = Color::rgba(0.0, 0.4, 0.45, 0.9);
+const PRESSED: Color = Color::rgba(0.0, 0.6, 0.65, 1.0);
+
+fn spawn_main_menu(mut commands: Commands, save: Res<SaveGame>) {
+    commands.spawn((MenuRoot, Camera2dBundle::default()));
+    let mut buttons = vec![];
+    if save.furthest.is_some() {
+        buttons.push(("Continue", MenuButton::Continue));
+    } else {
+        buttons.push(("New game", MenuButton::Continue));
+    }
+    buttons.push(("Select level", MenuButton::LevelSelect));
+    buttons.push(("Settings", MenuButton::Settings));
+    buttons.push(("Quit", MenuButton::Quit));
+    spawn_menu(&mut commands, MenuRoot, "Main menu", buttons);
+}
+
+fn spawn_level_select(mut commands: Commands, save: Res<SaveGame>, levels: Res<LevelList>) {
+    commands.spawn((MenuRoot, Camera2dBundle::default()));
+    let first = levels.0.first().copied();
+    let mut buttons = levels
+        .0
+        .iter()
+        .filter(|level| save.unlocked(**level) || Some(**level) == first)
+        .map(|level| (format!("Level {level}"), MenuButton::Level(*level)))
+        .collect::<Vec<_>>();
+    buttons.push(("Back".to_string(), MenuButton::Back));
+    spawn_menu(&mut commands, MenuRoot, "Select level", buttons);
+}
+
+fn spawn_settings(
+    mut commands: Commands,
+    map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    save: Res<SaveGame>,
+) {
+    commands.spawn((MenuRoot, Camera2dBundle::default()));
+    let mut buttons = Action::ALL
+        .iter()
+        .map(|action| {
+            let bindings = if rebinding.0 == Some(*action) {
+                "press a key".to_string()
+            } else {
+                map.bindings(*action)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            (
+                format!("{}: {bindings}", action.label()),
+                MenuButton::Rebind(*action),
+            )
+        })
+        .collect::<Vec<_>>();
+    let fullscreen = if save.settings.fullscreen {
+        "on"
+    } else {
+        "off"
+    };
+    buttons.push((format!("Fullscreen: {fullscreen}"), MenuButton::Fullscreen));
+    buttons.push(("Back".to_string(), MenuButton::Back));
+    spawn_menu(&mut commands, MenuRoot, "Settings", buttons);
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    spawn_menu(
+        &mut commands,
+        PauseRoot,
+        "Paused",
+        vec![
+            ("Resume", MenuButton::Resume),
+            ("Restart level", MenuButton::RestartLevel),
+            ("Quit to menu", MenuButton::QuitToMenu),
+        ],
+    );
+}
+
+fn spawn_menu<T: Into<String>>(
+    commands: &mut Commands,
+    root: impl Component,
+    title: &str,
+    buttons: Vec<(T, MenuButton)>,
+) {
+    commands
+        .spawn((
+            root,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..Default::default()
+                },
+                background_color: Color::rgba(0.0, 0.05, 0.08, 0.75).into(),
+                ..Default::default()
+            },
+        ))
+        .with_children(|p| {
+            p.spawn(
+                TextBundle::from_section(
+                    title,
+                    TextStyle {
+                        font_size: 64.0,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(24.0)),
+                    ..Default::default()
+                }),
+            );
+            for (text, button) in buttons {
+                p.spawn((
+                    button,
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(320.0),
+                            height: Val::Px(56.0),
+                            align_items: AlignItems::Center,
+                            justify_content: JustifyContent::Center,
+                            ..Default::default()
+                        },
+                        background_color: NORMAL.into(),
+                        ..Default::default()
+                    },
+                ))
+                .with_children(|p| {
+                    p.spawn(TextBundle::from_section(
+                        text,
+                        TextStyle {
+                            font_size: 28.0,
+                            color: Color::WHITE,
+                            ..Default::default()
+                        },
+                    ));
+                });
+            }
+        });
+}
+
+fn despawn<T: Component>(mut commands: Commands, roots: Query<Entity, With<T>>) {
+    for entity in roots.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+
+use crate::{
+    checkpoint::{ActiveCheckpoint, CheckpointSet, RespawnAtCheckpoint},
+    components::{air_station::AirStationSensor, loading::Loading},
+    player::Player,
+    sensor::Sensors,
+    simulation::SimulationSet,
+    GameState, Restart,
+};
+
+/// Extra air used per second while swimming at full speed, on top of one second of air
+/// per second. The diver has no separate boost, so the swimming effort stands in for it: a
+/// half-tilted stick swims at half speed and uses half the extra air.
+const SWIM_DRAIN: f32 = 1.0;
+/// Share of the tank an air station refills per second.
+const REFILL_RATE: f32 = 0.5;
+/// Share of the tank below which the gauge turns red.
+const LOW: f32 = 0.25;
+
+/// Air left in the diver's tank, in seconds of rest. Only levels with an `oxygen` capacity
+/// have it; running out restarts the level or respawns at the active checkpoint.
+#[derive(Resource, Clone, Debug)]
+pub struct Oxygen {
+    pub capacity: f32,
+    pub left: f32,
+}
+
+impl Oxygen {
+    pub fn new(capacity: f32) -> Self {
+        Self {
+            capacity,
+            left: capacity,
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        (self.left / self.capacity).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Component)]
+struct Gauge;
+
+#[derive(Component)]
+struct GaugeFill;
+
+pub struct OxygenPlugin;
+
+impl Plugin for OxygenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            breathe
+                .in_set(SimulationSet::Logic)
+                .before(CheckpointSet::Restore)
+                .run_if(resource_exists::<Oxygen>())
+                .run_if(resource_exists::<Player>())
+                .run_if(not(any_with_component::<Loading>())),
+        )
+        .add_systems(Startup, spawn_gauge)
+        .add_systems(Update, gauge);
+    }
+}
+
+fn breathe(
+    mut commands: Commands,
+    mut oxygen: ResMut<Oxygen>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut respawn: EventWriter<RespawnAtCheckpoint>,
+    player: Res<Player>,
+    time: Res<Time>,
+    sensors: Sensors,
+    stations: Query<Entity, With<AirStationSensor>>,
+    checkpoint: Option<Res<ActiveCheckpoint>>,
+    state: Res<State<GameState>>,
+) {
+    let delta = time.delta_seconds();
+    if stations
+        .iter()
+        .any(|station| sensors.player_inside(station))
+    {
+        oxygen.left = (oxygen.left + oxygen.capacity * REFILL_RATE * delta).min(oxygen.capacity);
+        return;
+    }
+
+    oxygen.left -= (1.0 + SWIM_DRAIN * player.move_vec.length()) * delta;
+    if oxygen.left > 0.0 {
+        return;
+    }
+
+    oxygen.left = oxygen.capacity;
+    if checkpoint.is_some() {
+        respawn.send(RespawnAtCheckpoint);
+    } else {
+        commands.insert_resource(Restart(state.get().clone()));
+        game_state.set(GameState::Restart);
+    }
+}
+
+fn spawn_gauge(mut commands: Commands) {
+    commands
+        .spawn((
+            Gauge,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    width: Val::Px(200.0),
+                    height: Val::Px(16.0),
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..Default::default()
+                },
+                border_color: Color::WHITE.into(),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        ))
+        .with_children(|p| {
+            p.spawn((
+                GaugeFill,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+fn gauge(
+    mut gauges: Query<&mut Visibility, With<Gauge>>,
+    mut fills: Query<(&mut Style, &mut BackgroundColor), With<GaugeFill>>,
+    oxygen: Option<Res<Oxygen>>,
+) {
+    for mut visibility in gauges.iter_mut() {
+        visibility.set_if_neq(if oxygen.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    let Some(oxygen) = oxygen.filter(|oxygen| oxygen.is_changed()) else {
+        return;
+    };
+    for (mut style, mut color) in fills.iter_mut() {
+        style.width = Val::Percent(oxygen.fraction() * 100.0);
+        color.0 = if oxygen.fraction() < LOW {
+            Color::rgb(0.9, 0.1, 0.1)
+        } else {
+            Color::rgb(0.3, 0.6, 1.0)
+        };
+    }
+}
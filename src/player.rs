@@ -9,6 +9,9 @@ use bevy_rapier2d::prelude::*;
 use crate::{
     components::loading::Loading,
     game_scene::{GameScene, GameSceneData, LoadGameScene},
+    input::{Action, ActionSet, ActionState},
+    menu::PauseState,
+    simulation::{Interpolated, SimulationSet},
     utils::reduce_to_root,
 };
 
@@ -32,6 +35,90 @@ pub struct ViewController {
     pub hide_player: bool,
 }
 
+/// Seconds a press waits to be consumed by an interaction.
+const INPUT_BUFFER: f32 = 0.15;
+
+/// Held state of an action plus its presses and releases, kept until a simulation step
+/// has seen them.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ActionInput {
+    held: bool,
+    /// Age of a press that no interaction has consumed yet.
+    buffered: Option<f32>,
+    /// A press started since the last simulation step.
+    started: bool,
+    released: bool,
+}
+
+impl ActionInput {
+    pub fn held(&self) -> bool {
+        self.held
+    }
+
+    pub fn just_pressed(&self) -> bool {
+        self.buffered.is_some()
+    }
+
+    pub fn just_released(&self) -> bool {
+        self.released
+    }
+
+    /// Whether a press started since the last simulation step, even if it already ended.
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    /// Takes the state a recorded simulation step saw, including a press that started and
+    /// ended between two steps.
+    pub fn restore(&mut self, held: bool, started: bool, released: bool) {
+        if started {
+            self.buffered = Some(0.0);
+        }
+        self.started |= started;
+        self.released |= released;
+        self.held = held;
+    }
+
+    /// Takes the buffered press, so that no other interaction reacts to it.
+    pub fn consume(&mut self) -> bool {
+        self.buffered.take().is_some()
+    }
+
+    fn update(&mut self, held: bool) {
+        if held && !self.held {
+            self.buffered = Some(0.0);
+            self.started = true;
+        }
+        self.released |= self.held && !held;
+        self.held = held;
+    }
+
+    fn age(&mut self, delta: f32) {
+        self.started = false;
+        self.released = false;
+        if let Some(age) = self.buffered.as_mut() {
+            *age += delta;
+            if *age > INPUT_BUFFER {
+                self.buffered = None;
+            }
+        }
+    }
+}
+
+/// Actions held by the player during one frame.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct PlayerInput {
+    pub interact: bool,
+    pub flashlight: bool,
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub click: bool,
+    /// Analog movement, used when no move action is held.
+    pub stick: Vec2,
+}
+
 #[derive(Resource)]
 pub struct Player {
     scene_data: GameSceneData,
@@ -39,13 +126,14 @@ pub struct Player {
     light: Option<Entity>,
     pub oxygen: Option<Entity>,
     pub socket: Option<Entity>,
-    pub is_action: bool,
-    pub is_space: bool,
+    pub interact: ActionInput,
+    pub is_flashlight: bool,
     is_up: bool,
     is_down: bool,
     is_left: bool,
     is_right: bool,
-    pub is_mouse: bool,
+    pub click: ActionInput,
+    stick: Vec2,
     pub direction: Direction,
     pub move_vec: Vec2,
     pub push_vec: Vec2,
@@ -53,6 +141,39 @@ pub struct Player {
     push_timer: f32,
     turnaround_timer: f32,
     light_timer: f32,
+    model_pose: Transform,
+}
+
+impl Player {
+    pub fn input(&self) -> PlayerInput {
+        PlayerInput {
+            interact: self.interact.held(),
+            flashlight: self.is_flashlight,
+            up: self.is_up,
+            down: self.is_down,
+            left: self.is_left,
+            right: self.is_right,
+            click: self.click.held(),
+            stick: self.stick,
+        }
+    }
+
+    pub fn set_input(&mut self, input: PlayerInput) {
+        self.interact.update(input.interact);
+        self.is_flashlight = input.flashlight;
+        self.is_up = input.up;
+        self.is_down = input.down;
+        self.is_left = input.left;
+        self.is_right = input.right;
+        self.click.update(input.click);
+        self.stick = input.stick;
+    }
+
+    pub fn clear_input(&mut self) {
+        self.set_input(PlayerInput::default());
+        self.interact = ActionInput::default();
+        self.click = ActionInput::default();
+    }
 }
 
 impl GameScene for Player {
@@ -63,13 +184,14 @@ impl GameScene for Player {
             light: None,
             oxygen: None,
             socket: None,
-            is_action: false,
-            is_space: false,
+            interact: ActionInput::default(),
+            is_flashlight: false,
             is_up: false,
             is_down: false,
             is_left: false,
             is_right: false,
-            is_mouse: false,
+            click: ActionInput::default(),
+            stick: Vec2::ZERO,
             direction: Direction::default(),
             move_vec: Vec2::ZERO,
             push_vec: Vec2::ZERO,
@@ -77,6 +199,7 @@ impl GameScene for Player {
             push_timer: 0.0,
             turnaround_timer: 0.0,
             light_timer: 0.0,
+            model_pose: Transform::IDENTITY,
         }
     }
 }
@@ -87,31 +210,47 @@ pub struct PlayerPhysics;
 #[derive(Component)]
 pub struct PlayerModel;
 
-#[derive(Component)]
-pub struct PlayerCollision {
-    pub other: Entity,
-}
+/// Action sampling into `Player`, runs in `PreUpdate` before any gameplay system.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlayerInputSet;
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
+        app.configure_sets(
+            PreUpdate,
+            PlayerInputSet
+                .after(ActionSet)
+                .run_if(resource_exists::<Player>())
+                .run_if(not(any_with_component::<Loading>()))
+                .run_if(in_state(PauseState::Running)),
+        )
+        .add_systems(PreUpdate, process_actions.in_set(PlayerInputSet))
+        .add_systems(
+            FixedUpdate,
+            (
+                process_movement.in_set(SimulationSet::Control),
+                age_input.after(SimulationSet::Logic),
+            )
+                .run_if(resource_exists::<Player>())
+                .run_if(not(any_with_component::<Loading>())),
+        )
+        .add_systems(
             Update,
             (
                 player_load.run_if(resource_exists::<LoadPlayer>()),
                 player_ready.run_if(resource_added::<Player>()),
                 (
-                    process_keyboard,
-                    process_movement,
+                    process_model,
                     process_view_controller,
                     process_light,
                     process_animations,
-                    process_collisions,
                 )
                     .run_if(resource_exists::<Player>())
                     .run_if(not(resource_added::<Player>()))
                     .run_if(not(any_with_component::<Loading>()))
+                    .run_if(in_state(PauseState::Running))
                     .after(player_ready),
             ),
         );
@@ -130,6 +269,8 @@ fn player_load(mut commands: Commands) {
             ExternalImpulse::default(),
             Velocity::default(),
             Collider::capsule_y(0.5, 0.5),
+            ReadMassProperties::default(),
+            Interpolated::new(Transform::from_xyz(0.0, 1.0, 0.0)),
         ))
         .id();
     commands
@@ -180,28 +321,30 @@ fn player_ready(
     }
 }
 
-fn process_keyboard(
-    keyboard_input: Res<Input<KeyCode>>,
-    mouse_input: Res<Input<MouseButton>>,
-    mut player: ResMut<Player>,
-) {
-    player.is_action = keyboard_input.pressed(KeyCode::E);
-    player.is_space = keyboard_input.pressed(KeyCode::Space);
-    player.is_up = keyboard_input.pressed(KeyCode::W);
-    player.is_left = keyboard_input.pressed(KeyCode::A);
-    player.is_down = keyboard_input.pressed(KeyCode::S);
-    player.is_right = keyboard_input.pressed(KeyCode::D);
-    player.is_mouse = mouse_input.pressed(MouseButton::Left);
+pub fn process_actions(actions: Res<ActionState>, mut player: ResMut<Player>) {
+    player.set_input(PlayerInput {
+        interact: actions.pressed(Action::Interact),
+        flashlight: actions.pressed(Action::Flashlight),
+        up: actions.pressed(Action::MoveUp),
+        down: actions.pressed(Action::MoveDown),
+        left: actions.pressed(Action::MoveLeft),
+        right: actions.pressed(Action::MoveRight),
+        click: actions.pressed(Action::Click),
+        stick: actions.stick(),
+    });
 }
 
-fn process_movement(
+/// Drops presses that no interaction consumed in time.
+fn age_input(time: Res<Time>, mut player: ResMut<Player>) {
+    let delta = time.delta_seconds();
+    player.interact.age(delta);
+    player.click.age(delta);
+}
+
+pub fn process_movement(
     time: Res<Time>,
     mut player: ResMut<Player>,
-    mut player_physics: Query<
-        (&mut ExternalImpulse, &Velocity, &Transform),
-        (With<PlayerPhysics>, Without<PlayerModel>),
-    >,
-    mut player_model: Query<&mut Transform, (With<PlayerModel>, Without<PlayerPhysics>)>,
+    mut player_physics: Query<(&mut ExternalImpulse, &Velocity, &Transform), With<PlayerPhysics>>,
 ) {
     let lin_speed = 10.0;
     let ang_speed = 12.0;
@@ -212,11 +355,16 @@ fn process_movement(
 
     let (mut impulse, velocity, transform) = player_physics.single_mut();
 
-    player.move_vec = Vec2 {
+    let digital = Vec2 {
         x: (player.is_right as i32 - player.is_left as i32) as f32,
         y: (player.is_up as i32 - player.is_down as i32) as f32,
     }
     .normalize_or_zero();
+    player.move_vec = if digital != Vec2::ZERO {
+        digital
+    } else {
+        player.stick.clamp_length_max(1.0)
+    };
 
     let is_moving = player.move_vec != Vec2::ZERO;
     let is_pushing = player.push_vec != Vec2::ZERO;
@@ -232,7 +380,7 @@ fn process_movement(
     }
 
     let lin_factor = player.swim_timer.min(lin_tmax) / lin_tmax;
-    let lin_speed = transform.up().xy() * lin_speed * lin_factor;
+    let lin_speed = transform.up().xy() * lin_speed * lin_factor * player.move_vec.length();
     let push_speed = player.push_vec * player.push_timer.min(push_tmax) / push_tmax;
     impulse.impulse = lin_speed + push_speed - velocity.linvel;
 
@@ -257,11 +405,22 @@ fn process_movement(
         player.direction = direction.clone();
         player.turnaround_timer = 0.0;
     }
+}
+
+/// Sways the diver model and places it at the interpolated pose of the physics body.
+fn process_model(
+    time: Res<Time>,
+    fixed_time: Res<Time<Fixed>>,
+    mut player: ResMut<Player>,
+    player_physics: Query<(&Transform, &Interpolated), (With<PlayerPhysics>, Without<PlayerModel>)>,
+    mut player_model: Query<&mut Transform, (With<PlayerModel>, Without<PlayerPhysics>)>,
+) {
+    let is_moving = player.move_vec != Vec2::ZERO;
 
-    let rotation_directon = if player.is_space {
+    let rotation_directon = if player.is_flashlight {
         FRAC_PI_2
     } else {
-        match direction {
+        match player.direction {
             Direction::Left => PI,
             Direction::Right => 0.0,
         }
@@ -273,15 +432,21 @@ fn process_movement(
     let swaying_speed = if is_moving { 0.0 } else { 1.7 };
     let translation_swaying = 0.1 * (swaying_speed * time.elapsed_seconds()).sin();
 
-    let mut player_model = player_model.single_mut();
-    player_model.rotation = player_model.rotation.slerp(
+    let pose = &mut player.model_pose;
+    pose.rotation = pose.rotation.slerp(
         Quat::from_axis_angle(Vec3::Y, rotation_directon + rotation_swaying),
         10.0 * time.delta_seconds(),
     );
-    player_model.translation = player_model.translation.lerp(
+    pose.translation = pose.translation.lerp(
         Vec3::from((0.0, translation_swaying, 0.0)),
         time.delta_seconds(),
     );
+
+    let (body, interpolated) = player_physics.single();
+    let smoothed = interpolated.at(fixed_time.overstep_percentage());
+    *player_model.single_mut() =
+        Transform::from_matrix(body.compute_matrix().inverse() * smoothed.compute_matrix())
+            * player.model_pose;
 }
 
 fn process_view_controller(
@@ -303,7 +468,7 @@ fn process_view_controller(
 }
 
 fn process_light(time: Res<Time>, mut player: ResMut<Player>, mut v: Query<&mut Visibility>) {
-    player.light_timer = if player.is_space {
+    player.light_timer = if player.is_flashlight {
         (player.light_timer + time.delta_seconds() * 5.0).min(1.0)
     } else {
         (player.light_timer - time.delta_seconds() * 20.0).max(0.0)
@@ -337,49 +502,52 @@ fn process_animations(player: Res<Player>, mut anim_player: Query<(&Name, &mut A
     }
 }
 
-pub fn process_collisions(
-    player: Query<Entity, With<PlayerPhysics>>,
-    collisions: Query<(Entity, &PlayerCollision)>,
-    mut commands: Commands,
-    mut collision_events: EventReader<CollisionEvent>,
-) {
-    let player = player.single();
-
-    for e in collision_events.read() {
-        let (other, started) = match e {
-            CollisionEvent::Started(e1, e2, _) => (
-                if e1 == &player {
-                    Some(e2)
-                } else if e2 == &player {
-                    Some(e1)
-                } else {
-                    None
-                },
-                true,
-            ),
-            CollisionEvent::Stopped(e1, e2, _) => (
-                if e1 == &player {
-                    Some(e2)
-                } else if e2 == &player {
-                    Some(e1)
-                } else {
-                    None
-                },
-                false,
-            ),
-        };
-
-        if let Some(other) = other.map(ToOwned::to_owned) {
-            let collision = PlayerCollision { other };
-            if started {
-                commands.spawn(collision);
-            } else {
-                for (e, c) in collisions.iter() {
-                    if c.other == collision.other {
-                        commands.entity(e).despawn_recursive();
-                    }
-                }
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_is_buffered() {
+        let mut input = ActionInput::default();
+        input.update(true);
+        assert!(input.held());
+        assert!(input.just_pressed());
+
+        // Holding on does not press again.
+        input.age(INPUT_BUFFER / 2.0);
+        input.update(true);
+        assert!(input.just_pressed());
+        input.age(INPUT_BUFFER);
+        input.update(true);
+        assert!(!input.just_pressed());
+        assert!(input.held());
+    }
+
+    #[test]
+    fn consume() {
+        let mut input = ActionInput::default();
+        input.update(true);
+        assert!(input.consume());
+        assert!(!input.just_pressed());
+        assert!(!input.consume());
+        assert!(input.held());
+    }
+
+    #[test]
+    fn press_outlives_release() {
+        let mut input = ActionInput::default();
+        input.update(true);
+        input.update(false);
+        assert!(!input.held());
+        assert!(input.just_pressed());
+        assert!(input.started());
+        assert!(input.just_released());
+
+        input.age(0.0);
+        assert!(!input.started());
+        assert!(!input.just_released());
+        assert!(input.just_pressed());
+        input.age(INPUT_BUFFER * 2.0);
+        assert!(!input.just_pressed());
     }
 }
@@ -0,0 +1,317 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use bevy::{app::AppExit, prelude::*};
+use bevy_mod_raycast::{primitives::Ray3d, CursorRay};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::loading::Loading,
+    headless::LockstepPlugin,
+    player::{Player, PlayerInput, PlayerInputSet},
+    simulation::{SimulationSet, TIMESTEP},
+};
+
+const RECORDING_VERSION: u32 = 3;
+
+/// Input seen by one simulation step. Actions are packed into `keys`, the gamepad stick is
+/// only kept while tilted and the cursor ray only while a code panel is being used.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct RecordedFrame {
+    keys: u8,
+    /// Presses and releases of interact and click since the previous step. A tap that ends
+    /// before the step is only found here.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    edges: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stick: Option<[f32; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ray: Option<[f32; 6]>,
+}
+
+impl RecordedFrame {
+    fn new(player: &Player, ray: Option<Ray3d>) -> Self {
+        let input = player.input();
+        let keys = pack(&[
+            input.interact,
+            input.flashlight,
+            input.up,
+            input.down,
+            input.left,
+            input.right,
+            input.click,
+        ]);
+        let edges = pack(&[
+            player.interact.started(),
+            player.interact.just_released(),
+            player.click.started(),
+            player.click.just_released(),
+        ]);
+        let stick = (input.stick != Vec2::ZERO).then_some(input.stick.to_array());
+        let ray = ray.map(|ray| {
+            let (o, d) = (ray.origin(), ray.direction());
+            [o.x, o.y, o.z, d.x, d.y, d.z]
+        });
+        Self {
+            keys,
+            edges,
+            stick,
+            ray,
+        }
+    }
+
+    fn input(&self) -> PlayerInput {
+        let key = |i: u8| self.keys & (1 << i) != 0;
+        PlayerInput {
+            interact: key(0),
+            flashlight: key(1),
+            up: key(2),
+            down: key(3),
+            left: key(4),
+            right: key(5),
+            click: key(6),
+            stick: self.stick.map_or(Vec2::ZERO, Vec2::from_array),
+        }
+    }
+
+    fn apply(&self, player: &mut Player) {
+        let input = self.input();
+        player.set_input(input);
+        let edge = |i: u8| self.edges & (1 << i) != 0;
+        player.interact.restore(input.interact, edge(0), edge(1));
+        player.click.restore(input.click, edge(2), edge(3));
+    }
+
+    fn ray(&self) -> Option<Ray3d> {
+        self.ray.map(|[ox, oy, oz, dx, dy, dz]| {
+            Ray3d::new(Vec3::new(ox, oy, oz), Vec3::new(dx, dy, dz))
+        })
+    }
+}
+
+fn pack(bits: &[bool]) -> u8 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |packed, (i, bit)| packed | ((*bit as u8) << i))
+}
+
+fn is_zero(bits: &u8) -> bool {
+    *bits == 0
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Recording {
+    pub version: u32,
+    /// Frame length the recording was made with.
+    pub timestep: f32,
+    pub level: usize,
+    pub seed: u64,
+    /// Run-length encoded simulation steps: repeat count and input.
+    pub frames: Vec<(u32, RecordedFrame)>,
+}
+
+impl Recording {
+    pub fn new(level: usize, seed: u64) -> Self {
+        Self {
+            version: RECORDING_VERSION,
+            timestep: TIMESTEP,
+            level,
+            seed,
+            frames: vec![],
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data =
+            fs::read_to_string(path).context(format!("Failed to read '{}'", path.display()))?;
+        let recording: Self = serde_json::from_str(&data)
+            .context(format!("Failed to parse recording '{}'", path.display()))?;
+        if recording.version != RECORDING_VERSION {
+            bail!("Unsupported recording version {}", recording.version);
+        }
+        if recording.timestep != TIMESTEP {
+            bail!("Recording uses a timestep of {}s", recording.timestep);
+        }
+        Ok(recording)
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn push(&mut self, frame: RecordedFrame) {
+        match self.frames.last_mut() {
+            Some((count, last)) if *last == frame => *count += 1,
+            _ => self.frames.push((1, frame)),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Recorder {
+    path: PathBuf,
+    recording: Recording,
+}
+
+#[derive(Resource)]
+struct Replay {
+    recording: Recording,
+    run: usize,
+    frame: u32,
+}
+
+pub enum ReplayPlugin {
+    /// Record gameplay input to a file, written on exit.
+    Record(PathBuf, Recording),
+    /// Feed recorded input to the player instead of the keyboard and mouse.
+    Replay(Recording),
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        // Input is recorded and replayed per simulation step, so the frame rate of a
+        // recording does not matter and it can be made at normal speed.
+        match self {
+            Self::Record(path, recording) => {
+                app.insert_resource(Recorder {
+                    path: path.clone(),
+                    recording: recording.clone(),
+                })
+                .add_systems(
+                    FixedUpdate,
+                    record
+                        .before(SimulationSet::Control)
+                        .run_if(resource_exists::<Player>())
+                        .run_if(not(any_with_component::<Loading>())),
+                )
+                .add_systems(Last, write);
+            }
+            Self::Replay(recording) => {
+                if !app.is_plugin_added::<LockstepPlugin>() {
+                    app.add_plugins(LockstepPlugin);
+                }
+                app.insert_resource(Replay {
+                    recording: recording.clone(),
+                    run: 0,
+                    frame: 0,
+                })
+                // The keyboard and mouse must not fight the recording over `Player`.
+                .configure_sets(PreUpdate, PlayerInputSet.run_if(|| false))
+                .add_systems(
+                    FixedUpdate,
+                    replay
+                        .before(SimulationSet::Control)
+                        .run_if(resource_exists::<Player>())
+                        .run_if(not(any_with_component::<Loading>())),
+                );
+            }
+        }
+    }
+}
+
+fn record(mut recorder: ResMut<Recorder>, player: Res<Player>, cursor_ray: Res<CursorRay>) {
+    let ray = player.view_controller.as_ref().and(**cursor_ray);
+    recorder.recording.push(RecordedFrame::new(&player, ray));
+}
+
+fn write(mut exit: EventReader<AppExit>, recorder: Res<Recorder>) {
+    if exit.read().last().is_none() {
+        return;
+    }
+    if let Err(e) = recorder.recording.write(&recorder.path) {
+        error!(
+            "Failed to write recording '{}': {e:#}",
+            recorder.path.display()
+        );
+    }
+}
+
+fn replay(
+    mut replay: ResMut<Replay>,
+    mut player: ResMut<Player>,
+    mut cursor_ray: ResMut<CursorRay>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let replay = &mut *replay;
+    let Some((count, frame)) = replay.recording.frames.get(replay.run) else {
+        info!("Replay finished");
+        exit.send(AppExit);
+        return;
+    };
+
+    frame.apply(&mut player);
+    // Also when the frame has none, so that the real mouse cannot hover anything.
+    **cursor_ray = frame.ray();
+
+    replay.frame += 1;
+    if replay.frame >= *count {
+        replay.frame = 0;
+        replay.run += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::game_scene::{GameScene, GameSceneData};
+
+    fn player() -> Player {
+        Player::from_scene_data(GameSceneData {
+            root: Entity::PLACEHOLDER,
+            animations: HashMap::new(),
+        })
+    }
+
+    fn round_trip(recording: &Recording) -> Recording {
+        serde_json::from_str(&serde_json::to_string(recording).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn tap_between_steps() {
+        let mut live = player();
+        // Pressed in one frame and released in the next, both before the step.
+        live.set_input(PlayerInput {
+            interact: true,
+            ..Default::default()
+        });
+        live.set_input(PlayerInput::default());
+        let mut recording = Recording::new(0, 0);
+        recording.push(RecordedFrame::new(&live, None));
+
+        let recording = round_trip(&recording);
+        let frame = recording.frames[0].1;
+        let mut replayed = player();
+        frame.apply(&mut replayed);
+        assert!(!replayed.interact.held());
+        assert!(replayed.interact.just_pressed());
+        assert!(replayed.interact.just_released());
+        assert!(!replayed.click.just_pressed());
+        assert_eq!(RecordedFrame::new(&replayed, None), frame);
+    }
+
+    #[test]
+    fn held_press() {
+        let mut live = player();
+        live.set_input(PlayerInput {
+            click: true,
+            right: true,
+            ..Default::default()
+        });
+        let mut recording = Recording::new(0, 0);
+        recording.push(RecordedFrame::new(&live, None));
+
+        let recording = round_trip(&recording);
+        let mut replayed = player();
+        recording.frames[0].1.apply(&mut replayed);
+        assert!(replayed.click.held());
+        assert!(replayed.click.just_pressed());
+        assert!(!replayed.click.just_released());
+        assert_eq!(replayed.input(), live.input());
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context, Result};
+use bevy::{prelude::*, window::WindowMode};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{cli::Cli, levels::LevelCompleted, GameState};
+
+const SAVE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Settings {
+    /// Toggled in the settings menu.
+    #[serde(default)]
+    pub fullscreen: bool,
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct SaveGame {
+    pub version: u32,
+    pub completed: BTreeSet<usize>,
+    pub furthest: Option<usize>,
+    /// Level number -> best completion time in seconds.
+    pub best_times: BTreeMap<usize, f32>,
+    pub settings: Settings,
+}
+
+impl Default for SaveGame {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            completed: BTreeSet::new(),
+            furthest: None,
+            best_times: BTreeMap::new(),
+            settings: Settings::default(),
+        }
+    }
+}
+
+impl SaveGame {
+    pub fn unlocked(&self, level: usize) -> bool {
+        self.completed.contains(&level) || self.furthest == Some(level)
+    }
+
+    fn path() -> PathBuf {
+        ProjectDirs::from("", "", "twg-2024-1")
+            .map(|dirs| dirs.data_dir().to_path_buf())
+            .unwrap_or_default()
+            .join("save.json")
+    }
+
+    fn load() -> Self {
+        let path = Self::path();
+        let Ok(data) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match Self::parse(&data) {
+            Ok(save) => save,
+            Err(e) => {
+                warn!("Save file '{}' is corrupt: {e:#}", path.display());
+                let _ = fs::rename(&path, path.with_extension("json.corrupt"));
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(data: &str) -> Result<Self> {
+        let mut value: Value = serde_json::from_str(data)?;
+        let version = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .context("Missing save version")? as u32;
+
+        if version > SAVE_VERSION {
+            bail!("Save version {version} is newer than supported {SAVE_VERSION}");
+        }
+        for version in version..SAVE_VERSION {
+            value = migrate(version, value)?;
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    fn write(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// Upgrades a save of `version` to `version + 1`. There is nothing to upgrade from yet.
+fn migrate(version: u32, _value: Value) -> Result<Value> {
+    bail!("Unknown save version {version}")
+}
+
+#[derive(Resource, Default)]
+struct LevelTimer(f32);
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveGame::load())
+            .init_resource::<LevelTimer>()
+            .add_systems(
+                Update,
+                (
+                    apply_settings.run_if(resource_changed::<SaveGame>()),
+                    enter_level.run_if(state_changed::<GameState>()),
+                    complete_level,
+                    write.run_if(resource_changed::<SaveGame>()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Applies the saved window mode at startup, unless the command line chose one, and again
+/// whenever it is toggled.
+fn apply_settings(
+    save: Res<SaveGame>,
+    cli: Res<Cli>,
+    mut windows: Query<&mut Window>,
+    mut applied: Local<Option<bool>>,
+) {
+    if *applied == Some(save.settings.fullscreen) {
+        return;
+    }
+    let startup = applied.is_none();
+    *applied = Some(save.settings.fullscreen);
+    if startup && cli.window_mode().is_some() {
+        return;
+    }
+    for mut window in windows.iter_mut() {
+        window.mode = if save.settings.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        };
+    }
+}
+
+fn enter_level(
+    time: Res<Time<Virtual>>,
+    state: Res<State<GameState>>,
+    mut timer: ResMut<LevelTimer>,
+    mut save: ResMut<SaveGame>,
+) {
+    let GameState::Level(level) = state.get() else {
+        return;
+    };
+    timer.0 = time.elapsed_seconds();
+    if save.furthest.map_or(true, |furthest| furthest < *level) {
+        save.furthest = Some(*level);
+    }
+}
+
+fn complete_level(
+    mut completed: EventReader<LevelCompleted>,
+    mut save: ResMut<SaveGame>,
+    time: Res<Time<Virtual>>,
+    timer: Res<LevelTimer>,
+) {
+    for LevelCompleted { level } in completed.read() {
+        let elapsed = time.elapsed_seconds() - timer.0;
+        save.completed.insert(*level);
+        let best = save.best_times.entry(*level).or_insert(elapsed);
+        *best = best.min(elapsed);
+    }
+}
+
+fn write(save: Res<SaveGame>) {
+    if save.is_added() {
+        return;
+    }
+    if let Err(e) = save.write() {
+        error!("Failed to write save file: {e:#}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_version_round_trips() {
+        let mut save = SaveGame::default();
+        save.completed.insert(3);
+        save.furthest = Some(4);
+        save.best_times.insert(3, 42.5);
+        save.settings.fullscreen = true;
+
+        let parsed = SaveGame::parse(&serde_json::to_string(&save).unwrap()).unwrap();
+        assert_eq!(parsed.completed, save.completed);
+        assert_eq!(parsed.furthest, save.furthest);
+        assert_eq!(parsed.best_times, save.best_times);
+        assert!(parsed.settings.fullscreen);
+    }
+
+    #[test]
+    fn unsupported_versions() {
+        assert!(SaveGame::parse(r#"{ "completed": [] }"#).is_err());
+        assert!(SaveGame::parse(&format!(r#"{{ "version": {} }}"#, SAVE_VERSION + 1)).is_err());
+        assert!(SaveGame::parse(r#"{ "version": 0 }"#).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier2d::{pipeline::CollisionEvent, plugin::RapierContext};
+
+use crate::{components::prop::Prop, player::PlayerPhysics, simulation::SimulationSet};
+
+/// Bodies overlapping a sensor, or touching a prop. Kept up to date from the collision
+/// events of the collider it is on.
+#[derive(Component, Default, Debug)]
+pub struct SensorContacts {
+    /// Collider inside -> the body it belongs to.
+    colliders: HashMap<Entity, Entity>,
+}
+
+impl SensorContacts {
+    pub fn contains(&self, body: Entity) -> bool {
+        self.colliders.values().any(|b| *b == body)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colliders.is_empty()
+    }
+
+    pub fn bodies(&self) -> HashSet<Entity> {
+        self.colliders.values().copied().collect()
+    }
+}
+
+/// Queries about what is inside the sensors.
+#[derive(SystemParam)]
+pub struct Sensors<'w, 's> {
+    contacts: Query<'w, 's, &'static SensorContacts>,
+    player: Query<'w, 's, Entity, With<PlayerPhysics>>,
+    props: Query<'w, 's, (), With<Prop>>,
+}
+
+impl<'w, 's> Sensors<'w, 's> {
+    pub fn contacts(&self, sensor: Entity) -> Option<&SensorContacts> {
+        self.contacts.get(sensor).ok()
+    }
+
+    pub fn player_inside(&self, sensor: Entity) -> bool {
+        let Ok(player) = self.player.get_single() else {
+            return false;
+        };
+        self.contacts(sensor)
+            .map_or(false, |contacts| contacts.contains(player))
+    }
+
+    pub fn props_inside(&self, sensor: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.contacts(sensor)
+            .into_iter()
+            .flat_map(SensorContacts::bodies)
+            .filter(|body| self.props.contains(*body))
+    }
+}
+
+pub struct SensorPlugin;
+
+impl Plugin for SensorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, track_contacts.in_set(SimulationSet::Collide));
+    }
+}
+
+pub fn track_contacts(
+    mut events: EventReader<CollisionEvent>,
+    mut contacts: Query<&mut SensorContacts>,
+    rapier: Res<RapierContext>,
+) {
+    for event in events.read() {
+        let (e1, e2, started) = match *event {
+            CollisionEvent::Started(e1, e2, _) => (e1, e2, true),
+            CollisionEvent::Stopped(e1, e2, _) => (e1, e2, false),
+        };
+        for (sensor, other) in [(e1, e2), (e2, e1)] {
+            let Ok(mut contacts) = contacts.get_mut(sensor) else {
+                continue;
+            };
+            if started {
+                let body = rapier.collider_parent(other).unwrap_or(other);
+                contacts.colliders.insert(other, body);
+            } else {
+                contacts.colliders.remove(&other);
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use bevy::{ecs::schedule::ExecutorKind, prelude::*};
+use bevy_rapier2d::plugin::{PhysicsSet, RapierConfiguration, TimestepMode};
+
+/// Length of one simulation step.
+pub const TIMESTEP: f32 = 1.0 / 60.0;
+
+/// Stages of a simulation step in `FixedUpdate`, in order.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    /// Player input turned into forces, before physics is stepped.
+    Control,
+    /// Contacts reported by the physics step.
+    Collide,
+    /// Puzzle components reacting to the player.
+    Interact,
+    /// Wiring, level progress and checkpoints.
+    Logic,
+}
+
+/// Pose of a simulated entity at the last two steps, so that rendering can blend between them.
+#[derive(Component, Clone, Copy)]
+pub struct Interpolated {
+    previous: Transform,
+    current: Transform,
+}
+
+impl Interpolated {
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            previous: transform,
+            current: transform,
+        }
+    }
+
+    /// Pose `alpha` of the way from the previous step to the current one.
+    pub fn at(&self, alpha: f32) -> Transform {
+        Transform {
+            translation: self
+                .previous
+                .translation
+                .lerp(self.current.translation, alpha),
+            rotation: self.previous.rotation.slerp(self.current.rotation, alpha),
+            scale: self.previous.scale.lerp(self.current.scale, alpha),
+        }
+    }
+}
+
+/// Runs gameplay and physics at a fixed rate, independent of the frame rate.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_duration(Duration::from_secs_f32(
+            TIMESTEP,
+        )))
+        .edit_schedule(FixedUpdate, |schedule| {
+            // The multi-threaded executor may run unordered systems in any order.
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        })
+        .configure_sets(
+            FixedUpdate,
+            (
+                SimulationSet::Control.before(PhysicsSet::SyncBackend),
+                (
+                    SimulationSet::Collide,
+                    SimulationSet::Interact,
+                    SimulationSet::Logic,
+                )
+                    .chain()
+                    .after(PhysicsSet::Writeback),
+            ),
+        )
+        .add_systems(Startup, configure_physics)
+        .add_systems(FixedUpdate, track.after(SimulationSet::Logic));
+    }
+}
+
+fn configure_physics(mut rapier: ResMut<RapierConfiguration>) {
+    // Everything floats under water.
+    rapier.gravity = Vec2::ZERO;
+    rapier.timestep_mode = TimestepMode::Fixed {
+        dt: TIMESTEP,
+        substeps: 1,
+    };
+}
+
+fn track(mut interpolated: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in interpolated.iter_mut() {
+        interpolated.previous = interpolated.current;
+        interpolated.current = *transform;
+    }
+}
@@ -1,4 +1,9 @@
 use bevy::prelude::*;
+use rand::rngs::StdRng;
+
+/// Source of randomness for gameplay, seeded from `--seed` when given.
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
 
 pub fn reduce_to_root<F: FnMut(T, Entity) -> T, T>(
     children: &Query<&Parent>,
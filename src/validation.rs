@@ -0,0 +1,140 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::GameState;
+
+/// Node of a level scene that does not have the structure its component needs.
+#[derive(Event, Clone, Debug)]
+pub struct SceneWiringError {
+    pub level: GameState,
+    pub node: String,
+    /// What was looked for, e.g. `a "sensor" collider`.
+    pub expected: String,
+    /// Component that was disabled because of the error.
+    pub entity: Option<Entity>,
+}
+
+impl SceneWiringError {
+    pub fn new(level: &GameState, node: &str, expected: impl Into<String>) -> Self {
+        Self {
+            level: level.clone(),
+            node: node.to_string(),
+            expected: expected.into(),
+            entity: None,
+        }
+    }
+
+    /// Disables the component on `entity` when the error is reported.
+    pub fn disable(mut self, entity: Entity) -> Self {
+        self.entity = Some(entity);
+        self
+    }
+}
+
+impl fmt::Display for SceneWiringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}: node '{}' expected {}",
+            self.level, self.node, self.expected
+        )
+    }
+}
+
+impl std::error::Error for SceneWiringError {}
+
+/// Component left out of the simulation because its scene is broken.
+#[derive(Component)]
+pub struct Disabled;
+
+/// Errors reported for the current level.
+#[derive(Resource, Default)]
+pub struct SceneErrors(pub Vec<SceneWiringError>);
+
+#[cfg(debug_assertions)]
+#[derive(Component)]
+struct ErrorOverlay;
+
+pub struct ValidationPlugin;
+
+impl Plugin for ValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SceneWiringError>()
+            .init_resource::<SceneErrors>()
+            .add_systems(
+                PostUpdate,
+                (
+                    clear.run_if(state_changed::<GameState>()),
+                    report.run_if(on_event::<SceneWiringError>()),
+                )
+                    .chain(),
+            );
+
+        #[cfg(debug_assertions)]
+        app.add_systems(
+            PostUpdate,
+            overlay
+                .after(report)
+                .run_if(resource_changed::<SceneErrors>()),
+        );
+    }
+}
+
+fn clear(mut errors: ResMut<SceneErrors>) {
+    errors.0.clear();
+}
+
+fn report(
+    mut commands: Commands,
+    mut reported: EventReader<SceneWiringError>,
+    mut errors: ResMut<SceneErrors>,
+) {
+    for error in reported.read() {
+        error!("{error}");
+        if let Some(entity) = error.entity {
+            commands.entity(entity).insert(Disabled);
+        }
+        errors.0.push(error.clone());
+    }
+}
+
+#[cfg(debug_assertions)]
+fn overlay(
+    mut commands: Commands,
+    errors: Res<SceneErrors>,
+    overlays: Query<Entity, With<ErrorOverlay>>,
+) {
+    for entity in overlays.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if errors.0.is_empty() {
+        return;
+    }
+
+    let text = errors
+        .0
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    commands.spawn((
+        ErrorOverlay,
+        TextBundle::from_section(
+            text,
+            TextStyle {
+                font_size: 20.0,
+                color: Color::RED,
+                ..Default::default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..Default::default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.7)),
+        ZIndex::Global(i32::MAX),
+    ));
+}
@@ -0,0 +1,404 @@
+use std::{collections::HashMap, mem};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{components::loading::Loading, simulation::SimulationSet};
+
+/// Logic node of the wiring graph. Node inputs are signal names: either
+/// `"<node name>:<port>"` of a component output or the name of another logic node.
+/// A signal name prefixed with `!` is inverted.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LogicNode {
+    And(Vec<String>),
+    Or(Vec<String>),
+    Not(String),
+    /// Turns on with `set` and stays on until `reset`.
+    Latch {
+        set: String,
+        #[serde(default)]
+        reset: Option<String>,
+    },
+    /// Turns on once `input` has been on for `seconds`, turns off together with `input`.
+    Delay {
+        input: String,
+        seconds: f32,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct WiringDescription {
+    #[serde(default)]
+    pub nodes: HashMap<String, LogicNode>,
+    /// Component input (`"<node name>:<port>"`) -> signal driving it.
+    #[serde(default)]
+    pub connections: HashMap<String, String>,
+}
+
+#[derive(Resource, Default, Clone)]
+pub struct WiringGraph {
+    description: WiringDescription,
+    states: HashMap<String, bool>,
+    delays: HashMap<String, f32>,
+    outputs: HashMap<String, bool>,
+    inputs: HashMap<String, bool>,
+    previous_inputs: HashMap<String, bool>,
+}
+
+impl WiringGraph {
+    pub fn new(description: WiringDescription) -> Self {
+        Self {
+            description,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_output(&mut self, node: &str, port: &str, value: bool) {
+        self.outputs.insert(format!("{node}:{port}"), value);
+    }
+
+    pub fn input(&self, node: &str, port: &str) -> Option<bool> {
+        self.inputs.get(&format!("{node}:{port}")).copied()
+    }
+
+    /// Whether the input turned on in the last step.
+    pub fn rose(&self, node: &str, port: &str) -> bool {
+        let input = format!("{node}:{port}");
+        self.inputs.get(&input) == Some(&true) && self.previous_inputs.get(&input) != Some(&true)
+    }
+
+    /// Advances stateful nodes by `delta` seconds and recomputes every connected input
+    /// from the outputs published since the last step.
+    pub fn step(&mut self, delta: f32) {
+        let mut cache = HashMap::new();
+        let connections = self
+            .description
+            .connections
+            .iter()
+            .map(|(input, signal)| (input.clone(), signal.clone()))
+            .collect::<Vec<_>>();
+
+        let mut inputs = HashMap::new();
+        for (input, signal) in connections {
+            let value = self.signal(&signal, delta, &mut cache);
+            inputs.insert(input, value);
+        }
+
+        // Stateful nodes must advance even if nothing is connected to them yet.
+        let nodes = self.description.nodes.keys().cloned().collect::<Vec<_>>();
+        for node in nodes {
+            self.signal(&node, delta, &mut cache);
+        }
+
+        self.previous_inputs = mem::replace(&mut self.inputs, inputs);
+    }
+
+    fn signal(&mut self, name: &str, delta: f32, cache: &mut HashMap<String, bool>) -> bool {
+        if let Some(name) = name.strip_prefix('!') {
+            return !self.signal(name, delta, cache);
+        }
+        if let Some(value) = self.outputs.get(name) {
+            return *value;
+        }
+        if let Some(value) = cache.get(name) {
+            return *value;
+        }
+        let Some(node) = self.description.nodes.get(name).cloned() else {
+            return false;
+        };
+
+        // Breaks feedback loops: a node that feeds itself sees its previous state.
+        let previous = self.states.get(name).copied().unwrap_or_default();
+        cache.insert(name.to_string(), previous);
+
+        let value = match node {
+            LogicNode::And(inputs) => inputs
+                .iter()
+                .fold(true, |acc, s| self.signal(s, delta, cache) && acc),
+            LogicNode::Or(inputs) => inputs
+                .iter()
+                .fold(false, |acc, s| self.signal(s, delta, cache) || acc),
+            LogicNode::Not(input) => !self.signal(&input, delta, cache),
+            LogicNode::Latch { set, reset } => {
+                let set = self.signal(&set, delta, cache);
+                let reset = reset.map_or(false, |reset| self.signal(&reset, delta, cache));
+                !reset && (set || previous)
+            }
+            LogicNode::Delay { input, seconds } => {
+                let timer = if self.signal(&input, delta, cache) {
+                    self.delays.get(name).copied().unwrap_or_default() + delta
+                } else {
+                    0.0
+                };
+                self.delays.insert(name.to_string(), timer);
+                timer >= seconds
+            }
+        };
+
+        self.states.insert(name.to_string(), value);
+        cache.insert(name.to_string(), value);
+        value
+    }
+}
+
+/// Component that takes part in the wiring graph under its `Name`.
+pub trait Wired: Component {
+    const OUTPUTS: &'static [&'static str];
+    const INPUTS: &'static [&'static str];
+
+    fn output(&self, port: &str) -> bool;
+    fn input(&mut self, port: &str, value: bool);
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WiringSet {
+    Collect,
+    Evaluate,
+    Apply,
+}
+
+pub trait WiringAppExt {
+    fn add_wired<T: Wired>(&mut self) -> &mut Self;
+}
+
+impl WiringAppExt for App {
+    fn add_wired<T: Wired>(&mut self) -> &mut Self {
+        self.add_systems(
+            FixedUpdate,
+            (
+                collect::<T>.in_set(WiringSet::Collect),
+                apply::<T>.in_set(WiringSet::Apply),
+            ),
+        )
+    }
+}
+
+pub struct WiringPlugin;
+
+impl Plugin for WiringPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(
+            FixedUpdate,
+            (WiringSet::Collect, WiringSet::Evaluate, WiringSet::Apply)
+                .chain()
+                .in_set(SimulationSet::Logic)
+                .run_if(resource_exists::<WiringGraph>())
+                .run_if(not(any_with_component::<Loading>())),
+        )
+        .add_systems(FixedUpdate, evaluate.in_set(WiringSet::Evaluate));
+    }
+}
+
+fn collect<T: Wired>(mut graph: ResMut<WiringGraph>, components: Query<(&Name, &T)>) {
+    for (name, component) in components.iter() {
+        for port in T::OUTPUTS {
+            graph.set_output(name.as_str(), port, component.output(port));
+        }
+    }
+}
+
+fn evaluate(time: Res<Time>, mut graph: ResMut<WiringGraph>) {
+    graph.step(time.delta_seconds());
+}
+
+fn apply<T: Wired>(graph: Res<WiringGraph>, mut components: Query<(&Name, &mut T)>) {
+    for (name, mut component) in components.iter_mut() {
+        for port in T::INPUTS {
+            if let Some(value) = graph.input(name.as_str(), port) {
+                component.input(port, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(nodes: &[(&str, LogicNode)], connections: &[(&str, &str)]) -> WiringGraph {
+        WiringGraph::new(WiringDescription {
+            nodes: nodes
+                .iter()
+                .map(|(name, node)| (name.to_string(), node.clone()))
+                .collect(),
+            connections: connections
+                .iter()
+                .map(|(input, signal)| (input.to_string(), signal.to_string()))
+                .collect(),
+        })
+    }
+
+    fn signals(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn gates() {
+        let mut graph = graph(
+            &[
+                ("and", LogicNode::And(signals(&["a:on", "b:on"]))),
+                ("or", LogicNode::Or(signals(&["a:on", "b:on"]))),
+                ("not", LogicNode::Not("a:on".into())),
+            ],
+            &[
+                ("gate:and", "and"),
+                ("gate:or", "or"),
+                ("gate:not", "not"),
+                ("gate:not_b", "!b:on"),
+                ("gate:nand", "!and"),
+            ],
+        );
+        let check = |graph: &WiringGraph, port, expected| {
+            assert_eq!(graph.input("gate", port), Some(expected), "{port}");
+        };
+
+        graph.set_output("a", "on", true);
+        graph.set_output("b", "on", false);
+        graph.step(0.1);
+        check(&graph, "and", false);
+        check(&graph, "or", true);
+        check(&graph, "not", false);
+        check(&graph, "not_b", true);
+        check(&graph, "nand", true);
+
+        graph.set_output("b", "on", true);
+        graph.step(0.1);
+        check(&graph, "and", true);
+        check(&graph, "not_b", false);
+        check(&graph, "nand", false);
+
+        graph.set_output("a", "on", false);
+        graph.set_output("b", "on", false);
+        graph.step(0.1);
+        check(&graph, "or", false);
+        check(&graph, "not", true);
+    }
+
+    #[test]
+    fn unknown_signals_are_off() {
+        let mut graph = graph(
+            &[],
+            &[("gate:open", "nowhere:on"), ("gate:close", "!nowhere")],
+        );
+        graph.step(0.1);
+        assert_eq!(graph.input("gate", "open"), Some(false));
+        assert_eq!(graph.input("gate", "close"), Some(true));
+        assert_eq!(graph.input("gate", "other"), None);
+    }
+
+    #[test]
+    fn latch() {
+        let mut graph = graph(
+            &[(
+                "latch",
+                LogicNode::Latch {
+                    set: "set:on".into(),
+                    reset: Some("reset:on".into()),
+                },
+            )],
+            &[("gate:open", "latch")],
+        );
+        let mut step = |set, reset| {
+            graph.set_output("set", "on", set);
+            graph.set_output("reset", "on", reset);
+            graph.step(0.1);
+            graph.input("gate", "open").unwrap()
+        };
+
+        assert!(!step(false, false));
+        assert!(step(true, false));
+        // Stays on after `set` drops.
+        assert!(step(false, false));
+        assert!(!step(false, true));
+        assert!(!step(false, false));
+        // `reset` wins over `set`.
+        assert!(!step(true, true));
+        assert!(step(true, false));
+    }
+
+    #[test]
+    fn delay() {
+        let mut graph = graph(
+            &[(
+                "delay",
+                LogicNode::Delay {
+                    input: "plate:on".into(),
+                    seconds: 1.0,
+                },
+            )],
+            &[("gate:open", "delay")],
+        );
+        let mut step = |on, delta| {
+            graph.set_output("plate", "on", on);
+            graph.step(delta);
+            graph.input("gate", "open").unwrap()
+        };
+
+        assert!(!step(true, 0.4));
+        assert!(!step(true, 0.4));
+        assert!(step(true, 0.4));
+        assert!(step(true, 0.4));
+
+        // Dropping the input turns it off and starts the count over.
+        assert!(!step(false, 0.4));
+        assert!(!step(true, 0.4));
+        assert!(!step(true, 0.4));
+        assert!(step(true, 0.4));
+    }
+
+    #[test]
+    fn delay_advances_unconnected() {
+        let mut graph = graph(
+            &[(
+                "delay",
+                LogicNode::Delay {
+                    input: "plate:on".into(),
+                    seconds: 0.5,
+                },
+            )],
+            &[],
+        );
+        graph.set_output("plate", "on", true);
+        graph.step(0.3);
+        graph.step(0.3);
+        graph.description.connections = [("gate:open".to_string(), "delay".to_string())].into();
+        graph.step(0.0);
+        assert_eq!(graph.input("gate", "open"), Some(true));
+    }
+
+    #[test]
+    fn rose() {
+        let mut graph = graph(&[], &[("level:complete", "exit:on")]);
+        let mut step = |on| {
+            graph.set_output("exit", "on", on);
+            graph.step(0.1);
+            graph.rose("level", "complete")
+        };
+
+        assert!(!step(false));
+        assert!(step(true));
+        assert!(!step(true));
+        assert!(!step(false));
+        assert!(step(true));
+    }
+
+    #[test]
+    fn feedback_loop() {
+        // Holds itself on once `button` has been pressed.
+        let mut graph = graph(
+            &[("hold", LogicNode::Or(signals(&["button:on", "hold"])))],
+            &[("gate:open", "hold")],
+        );
+        let mut step = |on| {
+            graph.set_output("button", "on", on);
+            graph.step(0.1);
+            graph.input("gate", "open").unwrap()
+        };
+
+        assert!(!step(false));
+        assert!(step(true));
+        assert!(step(false));
+        assert!(step(false));
+    }
+}
@@ -0,0 +1,453 @@
+use std::{
+    collections::{HashMap, LinkedList},
+    mem,
+};
+
+use bevy::{
+    ecs::{schedule::apply_state_transition, system::CommandQueue},
+    prelude::*,
+    tasks::tick_global_task_pools_on_main_thread,
+};
+use bevy_rapier2d::{
+    dynamics::Velocity,
+    geometry::{ActiveEvents, Collider, Sensor},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use serde_json::{json, Value};
+use twg_2024_1::{
+    components::{code::Code, loading::Loading},
+    game_scene::SceneComponents,
+    headless::{headless_plugins, HeadlessPlugin},
+    input::InputMapPlugin,
+    levels::{
+        data::{load_manifest, ComponentDescription},
+        lvl0::Level0,
+        LevelRoot, LoadLevel,
+    },
+    player::{Player, PlayerPhysics},
+    save::SaveGame,
+    sensor::SensorContacts,
+    utils::GameRng,
+    wiring::WiringGraph,
+    GamePlugins, GameState,
+};
+
+/// Frames a level gets to finish loading before a test gives up.
+const LOAD_FRAMES: u32 = 600;
+
+/// Level made of the placeholder scene `lvl0.glb`, for tests that spawn their own puzzle
+/// components instead of needing the level scenes.
+pub const SANDBOX: usize = 100;
+
+/// Open water of the sandbox, clear of its cube and floor.
+pub const SANDBOX_SPAWN: Vec2 = Vec2::new(5.0, 5.0);
+
+/// Headless game driven frame by frame with scripted input.
+pub struct Harness {
+    pub app: App,
+    /// Every state the game has been in, in order.
+    pub states: Vec<GameState>,
+    /// Animations of spawned components, kept alive for the whole test.
+    animations: HashMap<String, Handle<AnimationClip>>,
+    /// Components of a rebuilt level, spawned again whenever it reloads.
+    components: Vec<(String, ComponentDescription)>,
+}
+
+impl Harness {
+    pub fn sandbox() -> Self {
+        let mut game = GamePlugins::new().expect("Failed to load levels");
+        let description = serde_json::from_value(json!({ "scene": "lvl0.glb", "index": 0 }))
+            .expect("Invalid sandbox description");
+        game.levels = game.levels.with_description(SANDBOX, description);
+
+        let mut harness = Self::start(SANDBOX, game, |_| {});
+        // The player starts inside the cube of the scene.
+        harness.move_player(SANDBOX_SPAWN);
+        harness
+    }
+
+    /// Level `level` of `levels.json` on the sandbox scene. Its wiring is kept as is, its
+    /// components are spawned in a row through open water instead of being found in its scene.
+    pub fn rebuilt(level: usize) -> Self {
+        let mut description = load_manifest("levels.json")
+            .expect("Failed to load levels")
+            .remove(&level)
+            .unwrap_or_else(|| panic!("No level {level}"));
+        let mut components = mem::take(&mut description.components)
+            .into_iter()
+            .collect::<Vec<_>>();
+        components.sort_by(|(a, _), (b, _)| a.cmp(b));
+        description.scene = "lvl0.glb".to_string();
+        description.index = 0;
+
+        let mut game = GamePlugins::new().expect("Failed to load levels");
+        game.levels = game.levels.with_description(level, description);
+
+        let mut harness = Self::start(level, game, |_| {});
+        harness.components = components;
+        harness.respawn();
+        harness
+    }
+
+    /// Level 0 on the sandbox scene, with stand-ins for the lever and fan nodes of its scene.
+    pub fn level0() -> Self {
+        let game = GamePlugins::new().expect("Failed to load levels");
+        let mut harness = Self::start(0, game, |app| {
+            // Level 0 names its scene itself.
+            app.add_systems(
+                StateTransition,
+                (|mut commands: Commands| {
+                    commands.insert_resource(LoadLevel::new::<Level0>("lvl0.glb", 0));
+                })
+                .after(apply_state_transition::<GameState>)
+                .run_if(resource_added::<LoadLevel>()),
+            );
+        });
+
+        let world = &mut harness.app.world;
+        let root = world.resource::<LevelRoot>().0;
+        for (name, radius, position) in [
+            ("lever1_sensor", 2.0, SANDBOX_SPAWN + Vec2::X * 6.0),
+            ("pusher1", 3.0, SANDBOX_SPAWN + Vec2::X * 14.0),
+        ] {
+            world
+                .spawn((
+                    Name::new(name),
+                    SpatialBundle::from_transform(Transform::from_translation(
+                        position.extend(0.0),
+                    )),
+                    Collider::ball(radius),
+                    Sensor,
+                    ActiveEvents::COLLISION_EVENTS,
+                    SensorContacts::default(),
+                ))
+                .set_parent(root);
+        }
+        // Level 0 sets its nodes up once its scene is in.
+        let level = world.remove_resource::<Level0>().unwrap();
+        world.insert_resource(level);
+
+        harness.move_player(SANDBOX_SPAWN);
+        harness
+    }
+
+    fn start(level: usize, game: GamePlugins, configure: impl FnOnce(&mut App)) -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            headless_plugins(),
+            HeadlessPlugin { frames: None },
+            game.set(InputMapPlugin { persist: false }),
+        ))
+        .insert_resource(GameRng(StdRng::seed_from_u64(0)))
+        .init_resource::<SaveGame>()
+        .insert_resource(State::new(GameState::Level(level)));
+        configure(&mut app);
+        while app.plugins_state() == bevy::app::PluginsState::Adding {
+            tick_global_task_pools_on_main_thread();
+        }
+        app.finish();
+        app.cleanup();
+
+        let mut harness = Self {
+            app,
+            states: vec![],
+            animations: HashMap::new(),
+            components: vec![],
+        };
+        assert!(
+            harness.wait_until(LOAD_FRAMES, Self::loaded),
+            "Level {level} did not load"
+        );
+        harness
+    }
+
+    /// Spawns the components of a rebuilt level, which its reloads leave out.
+    pub fn respawn(&mut self) {
+        for (i, (name, component)) in self.components.clone().into_iter().enumerate() {
+            let position = SANDBOX_SPAWN + Vec2::X * 6.0 * (i + 1) as f32;
+            let extras = Value::Object(component.extras);
+            self.spawn_component(&name, &component.kind, extras, position);
+        }
+        self.move_player(SANDBOX_SPAWN);
+    }
+
+    /// Spawns a node at `position` into the level with the child nodes of `parts` and
+    /// attaches the scene component `kind` to it, configured by `extras` like the glTF
+    /// extras of a node. Reloading the level removes it again.
+    pub fn spawn_component(&mut self, name: &str, kind: &str, extras: Value, position: Vec2) {
+        self.spawn(name, kind, extras, position, parts(kind));
+    }
+
+    /// Spawns a component like `spawn_component`, but without any child nodes.
+    pub fn spawn_bare(&mut self, name: &str, kind: &str, extras: Value, position: Vec2) {
+        self.spawn(name, kind, extras, position, vec![]);
+    }
+
+    fn spawn(
+        &mut self,
+        name: &str,
+        kind: &str,
+        extras: Value,
+        position: Vec2,
+        parts: Vec<(String, Part, Vec3)>,
+    ) {
+        let Value::Object(extras) = extras else {
+            panic!("Extras of {name} are not an object");
+        };
+        let world = &mut self.app.world;
+        for animation in ["gate_open", "switch_pull"] {
+            if !self.animations.contains_key(animation) {
+                let clip = world
+                    .resource_mut::<Assets<AnimationClip>>()
+                    .add(AnimationClip::default());
+                self.animations.insert(animation.to_string(), clip);
+            }
+        }
+        let material = world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial::default());
+        let root = world.resource::<LevelRoot>().0;
+
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, world);
+        let mut entity = commands.spawn((
+            Name::new(name.to_string()),
+            SpatialBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+            AnimationPlayer::default(),
+        ));
+        entity.set_parent(root).with_children(|node| {
+            for (part, shape, offset) in parts {
+                let mut child = node.spawn((
+                    Name::new(format!("{name}.{part}")),
+                    SpatialBundle::from_transform(Transform::from_translation(offset)),
+                ));
+                match shape {
+                    Part::Sensor => {
+                        child.insert((
+                            Collider::ball(1.0),
+                            Sensor,
+                            ActiveEvents::COLLISION_EVENTS,
+                            SensorContacts::default(),
+                        ));
+                    }
+                    Part::Solid => {
+                        child.insert(Collider::cuboid(0.5, 2.0));
+                    }
+                    Part::Mesh => {
+                        child.insert((Handle::<Mesh>::default(), material.clone()));
+                    }
+                    Part::Node => {}
+                }
+            }
+        });
+        if let Err(expected) =
+            world
+                .resource::<SceneComponents>()
+                .insert(&mut entity, kind, extras, &self.animations)
+        {
+            panic!("{name} expected {expected}");
+        }
+        queue.apply(world);
+
+        assert!(
+            self.wait_until(LOAD_FRAMES, Self::loaded),
+            "{name} did not load"
+        );
+    }
+
+    pub fn frame(&mut self) {
+        self.app.update();
+        let state = self.state();
+        if self.states.last() != Some(&state) {
+            self.states.push(state);
+        }
+    }
+
+    pub fn run(&mut self, frames: u32) {
+        for _ in 0..frames {
+            self.frame();
+        }
+    }
+
+    /// Runs until `condition` holds, at most `frames` frames.
+    pub fn wait_until(&mut self, frames: u32, condition: impl Fn(&mut Self) -> bool) -> bool {
+        for _ in 0..frames {
+            if condition(self) {
+                return true;
+            }
+            self.frame();
+        }
+        condition(self)
+    }
+
+    pub fn state(&self) -> GameState {
+        self.app.world.resource::<State<GameState>>().get().clone()
+    }
+
+    pub fn loaded(&mut self) -> bool {
+        let world = &mut self.app.world;
+        world.contains_resource::<Player>()
+            && (world.contains_resource::<WiringGraph>() || world.contains_resource::<Level0>())
+            && world
+                .query_filtered::<(), With<Loading>>()
+                .iter(world)
+                .next()
+                .is_none()
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().press(key);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().release(key);
+    }
+
+    pub fn click(&mut self) {
+        let mut mouse = self.app.world.resource_mut::<Input<MouseButton>>();
+        mouse.press(MouseButton::Left);
+        self.frame();
+        let mut mouse = self.app.world.resource_mut::<Input<MouseButton>>();
+        mouse.release(MouseButton::Left);
+        self.frame();
+    }
+
+    /// Holds `key` for `frames` frames.
+    pub fn hold(&mut self, key: KeyCode, frames: u32) {
+        self.press(key);
+        self.run(frames);
+        self.release(key);
+        self.frame();
+    }
+
+    /// Moves the player onto the sensor of the named puzzle component.
+    pub fn teleport(&mut self, name: &str) {
+        let target = self.sensor(name);
+        let translation = self
+            .app
+            .world
+            .get::<GlobalTransform>(target)
+            .unwrap()
+            .translation();
+        self.move_player(translation.xy());
+    }
+
+    pub fn move_player(&mut self, position: Vec2) {
+        let world = &mut self.app.world;
+        let (mut transform, mut velocity) = world
+            .query_filtered::<(&mut Transform, &mut Velocity), With<PlayerPhysics>>()
+            .single_mut(world);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        *velocity = Velocity::zero();
+        // Let physics report the new contacts.
+        self.run(5);
+    }
+
+    /// Activates the code panel at the player's position and types `digits` on it.
+    pub fn enter_code(&mut self, name: &str, digits: &str) {
+        self.hold(KeyCode::E, 1);
+        for digit in digits.bytes() {
+            self.component_mut::<Code>(name).press_button(digit - b'0');
+            self.frame();
+        }
+        // The screen blinks before the result is accepted.
+        self.run(30);
+    }
+
+    /// Pose of the simulated player body.
+    pub fn player(&mut self) -> Transform {
+        let world = &mut self.app.world;
+        *world
+            .query_filtered::<&Transform, With<PlayerPhysics>>()
+            .single(world)
+    }
+
+    pub fn component<T: Component>(&mut self, name: &str) -> &T {
+        let entity = self.entity(name);
+        self.app.world.get::<T>(entity).unwrap()
+    }
+
+    pub fn component_mut<T: Component>(&mut self, name: &str) -> Mut<'_, T> {
+        let entity = self.entity(name);
+        self.app.world.get_mut::<T>(entity).unwrap()
+    }
+
+    pub fn entity(&mut self, name: &str) -> Entity {
+        let world = &mut self.app.world;
+        world
+            .query::<(Entity, &Name)>()
+            .iter(world)
+            .find(|(_, n)| n.as_str() == name)
+            .map(|(entity, _)| entity)
+            .unwrap_or_else(|| panic!("No entity named '{name}'"))
+    }
+
+    fn sensor(&mut self, name: &str) -> Entity {
+        let root = self.entity(name);
+        let world = &self.app.world;
+        let mut stack = LinkedList::from([root]);
+        while let Some(current) = stack.pop_back() {
+            let is_sensor = world
+                .get::<Name>(current)
+                .map_or(false, |name| name.contains("sensor"));
+            if is_sensor && world.get::<Collider>(current).is_some() {
+                return current;
+            }
+            if let Some(children) = world.get::<Children>(current) {
+                stack.extend(children.iter());
+            }
+        }
+        root
+    }
+}
+
+/// Kind of a child node of a spawned component.
+enum Part {
+    Sensor,
+    Solid,
+    Mesh,
+    Node,
+}
+
+/// Child nodes the scene component `kind` looks for below its node, named like in the
+/// level scenes.
+fn parts(kind: &str) -> Vec<(String, Part, Vec3)> {
+    let part = |name: &str, part, offset| (name.to_string(), part, offset);
+    match kind {
+        "switch" => vec![
+            part("sensor", Part::Sensor, Vec3::ZERO),
+            part("red", Part::Mesh, Vec3::Y),
+            part("green", Part::Mesh, Vec3::Y),
+        ],
+        "security_camera" => vec![
+            part("sensor", Part::Sensor, Vec3::ZERO),
+            part("cone", Part::Mesh, Vec3::ZERO),
+        ],
+        "code" => {
+            let mut parts = vec![
+                part("sensor", Part::Sensor, Vec3::ZERO),
+                part("screen", Part::Mesh, Vec3::ZERO),
+            ];
+            for digit in 1..=4 {
+                for segment in 1..=7 {
+                    let name = format!("segment_{digit}_{segment}");
+                    parts.push(part(&name, Part::Node, Vec3::ZERO));
+                }
+            }
+            // Laid out like a phone keypad.
+            for number in 0..=9u8 {
+                let (column, row) = match number {
+                    0 => (1, 3),
+                    n => ((n - 1) % 3, (n - 1) / 3),
+                };
+                let offset = Vec3::new(column as f32, -(row as f32), 0.0) * 0.3;
+                parts.push(part(&format!("btn_{number}"), Part::Node, offset));
+            }
+            parts
+        }
+        "gate" => vec![part("physics", Part::Solid, Vec3::ZERO)],
+        "fan" => vec![part("pusher", Part::Sensor, Vec3::ZERO)],
+        _ => vec![part("sensor", Part::Sensor, Vec3::ZERO)],
+    }
+}
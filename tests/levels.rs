@@ -0,0 +1,469 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_rapier2d::geometry::ColliderMassProperties;
+use serde_json::{json, Value};
+use twg_2024_1::{
+    components::{
+        code::Code,
+        fan::Fan,
+        gate::Gate,
+        prop::{Prop, PropCollider},
+        security_camera::SecurityCamera,
+        socket::Socket,
+        switch::{Switch, SwitchCountdown, SwitchMode},
+    },
+    input::{Action, Binding, InputMap},
+    interaction::InteractionTarget,
+    levels::lvl0::Level0,
+    oxygen::Oxygen,
+    player::Player,
+    simulation::TIMESTEP,
+    validation::{Disabled, SceneErrors},
+    GameState,
+};
+
+mod common;
+
+use common::{Harness, SANDBOX, SANDBOX_SPAWN};
+
+fn pull(h: &mut Harness, switch: &str) {
+    h.teleport(switch);
+    h.hold(KeyCode::E, 2);
+    assert!(
+        h.wait_until(60, |h| h.component::<Switch>(switch).activated()),
+        "{switch} was not pulled"
+    );
+}
+
+/// Sandbox with a switch named `switch.1` out of the player's reach.
+fn switch(extras: Value) -> Harness {
+    let mut h = Harness::sandbox();
+    h.spawn_component("switch.1", "switch", extras, SANDBOX_SPAWN + Vec2::X * 5.0);
+    h
+}
+
+fn solve(h: &mut Harness, code: &str, digits: &str) {
+    h.teleport(code);
+    h.enter_code(code, digits);
+    assert!(
+        h.component::<Code>(code).activated(),
+        "{code} did not accept {digits}"
+    );
+}
+
+fn connect(h: &mut Harness, from: &str, to: &str) {
+    h.teleport(from);
+    h.hold(KeyCode::E, 1);
+    h.teleport(to);
+    h.hold(KeyCode::E, 1);
+    assert!(
+        h.component::<Socket>(from).connected(),
+        "{from} not connected"
+    );
+    assert!(h.component::<Socket>(to).connected(), "{to} not connected");
+}
+
+fn gate_opens(h: &mut Harness, gate: &str) {
+    assert!(
+        h.wait_until(10, |h| h.component::<Gate>(gate).opened()),
+        "{gate} did not open"
+    );
+}
+
+fn fan_stops(h: &mut Harness, fan: &str) {
+    assert!(
+        h.wait_until(10, |h| !h.component::<Fan>(fan).spinning),
+        "{fan} still spinning"
+    );
+}
+
+fn camera_disabled(h: &mut Harness, camera: &str) {
+    assert!(
+        h.wait_until(10, |h| !h.component::<SecurityCamera>(camera).active),
+        "{camera} still active"
+    );
+}
+
+fn moves_to(h: &mut Harness, state: GameState) {
+    assert!(
+        h.wait_until(10, |h| h.state() == state),
+        "Expected {state:?}, got {:?}",
+        h.state()
+    );
+}
+
+fn caught(h: &mut Harness, camera: &str, level: usize) {
+    h.teleport(camera);
+    assert!(
+        h.wait_until(900, |h| h.states.contains(&GameState::Restart)),
+        "{camera} did not restart the level"
+    );
+    assert!(h.wait_until(600, Harness::loaded), "Level did not reload");
+    assert_eq!(h.state(), GameState::Level(level));
+    // The reload dropped the camera along with the rest of the level.
+    assert!(h
+        .app
+        .world
+        .query::<&SecurityCamera>()
+        .iter(&h.app.world)
+        .next()
+        .is_none());
+    h.respawn();
+    assert!(!h.component::<SecurityCamera>(camera).triggered);
+}
+
+#[test]
+fn level0_solve() {
+    let mut h = Harness::level0();
+    let pushed = |h: &mut Harness| {
+        h.teleport("pusher1");
+        h.app.world.resource::<Player>().push_vec
+    };
+    assert!(pushed(&mut h).y > 0.0, "pusher1 does not push");
+
+    // The lever can only be pulled facing left.
+    h.teleport("lever1_sensor");
+    h.hold(KeyCode::E, 2);
+    assert!(h.app.world.resource::<Level0>().pusher1_active);
+    h.press(KeyCode::A);
+    h.press(KeyCode::E);
+    assert!(
+        h.wait_until(30, |h| !h.app.world.resource::<Level0>().pusher1_active),
+        "lever1 was not pulled"
+    );
+    h.release(KeyCode::A);
+    h.release(KeyCode::E);
+
+    assert_eq!(pushed(&mut h), Vec2::ZERO, "pusher1 still pushes");
+}
+
+// Level 0 has no security camera to get caught by.
+
+#[test]
+fn level1_solve() {
+    let mut h = Harness::rebuilt(1);
+    pull(&mut h, "switch.1");
+    camera_disabled(&mut h, "camera.1");
+    pull(&mut h, "switch.2");
+    gate_opens(&mut h, "gate.1");
+    solve(&mut h, "code.1", "1234");
+    connect(&mut h, "socket_start.1", "socket_end.1");
+    // Level 1 wires no exit, so the puzzles must stay solved.
+    h.run(60);
+    assert!(!h.component::<SecurityCamera>("camera.1").active);
+    assert!(h.component::<Gate>("gate.1").opened());
+    assert!(!h.states.contains(&GameState::Restart));
+}
+
+#[test]
+fn level1_caught() {
+    // The camera of level 1 is not wired to restart the level.
+    let mut h = Harness::rebuilt(1);
+    h.teleport("camera.1");
+    assert!(h.wait_until(900, |h| h.component::<SecurityCamera>("camera.1").triggered));
+    h.run(10);
+    assert!(!h.states.contains(&GameState::Restart));
+    assert_eq!(h.state(), GameState::Level(1));
+}
+
+#[test]
+fn level2_solve() {
+    let mut h = Harness::rebuilt(2);
+    solve(&mut h, "code.002", "1824");
+    fan_stops(&mut h, "fan.002");
+    pull(&mut h, "switch.003");
+    camera_disabled(&mut h, "camera.002");
+    connect(&mut h, "socket_start.002", "socket_end.002");
+    moves_to(&mut h, GameState::Level(3));
+}
+
+#[test]
+fn level2_caught() {
+    let mut h = Harness::rebuilt(2);
+    caught(&mut h, "camera.002", 2);
+}
+
+#[test]
+fn level3_solve() {
+    let mut h = Harness::rebuilt(3);
+    solve(&mut h, "code.004", "3028");
+    fan_stops(&mut h, "fan.004");
+    solve(&mut h, "code.005", "8824");
+    fan_stops(&mut h, "fan.006");
+    pull(&mut h, "switch.005");
+    gate_opens(&mut h, "gate.002");
+    pull(&mut h, "switch.006");
+    fan_stops(&mut h, "fan.008");
+    connect(&mut h, "socket_start.003", "socket_end.003");
+    moves_to(&mut h, GameState::Level(4));
+}
+
+// Level 3 has no security camera to get caught by.
+
+#[test]
+fn level4_solve() {
+    let mut h = Harness::rebuilt(4);
+    assert_eq!(h.app.world.resource::<Oxygen>().capacity, 300.0);
+    pull(&mut h, "switch.008");
+    camera_disabled(&mut h, "camera.003");
+    solve(&mut h, "code.003", "9835");
+    gate_opens(&mut h, "gate.003");
+    solve(&mut h, "code.008", "9845");
+    gate_opens(&mut h, "gate.004");
+    solve(&mut h, "code.006", "0152");
+    gate_opens(&mut h, "gate.005");
+    solve(&mut h, "code.007", "5489");
+    fan_stops(&mut h, "fan.012");
+    pull(&mut h, "switch.004");
+    fan_stops(&mut h, "fan.010");
+    pull(&mut h, "switch.007");
+    gate_opens(&mut h, "gate.007");
+    pull(&mut h, "switch.009");
+    gate_opens(&mut h, "gate.006");
+    pull(&mut h, "switch.010");
+    fan_stops(&mut h, "fan.014");
+    connect(&mut h, "socket_start.004", "socket_end.004");
+    moves_to(&mut h, GameState::Level(2));
+}
+
+#[test]
+fn level4_caught() {
+    let mut h = Harness::rebuilt(4);
+    caught(&mut h, "camera.003", 4);
+}
+
+#[test]
+fn frame_rate_independent() {
+    // Both runs cover 60 simulation steps.
+    let step = Duration::from_secs_f32(TIMESTEP);
+    let moved = [(step * 2, 30), (step / 2, 120)].map(|(frame, frames)| {
+        let mut h = Harness::sandbox();
+        h.app
+            .insert_resource(TimeUpdateStrategy::ManualDuration(frame));
+        let start = h.player().translation;
+        h.press(KeyCode::D);
+        h.run(frames);
+        h.player().translation - start
+    });
+    assert_ne!(moved[0], Vec3::ZERO);
+    assert_eq!(moved[0], moved[1]);
+}
+
+#[test]
+fn rebound_action() {
+    let mut h = Harness::sandbox();
+    h.app
+        .world
+        .resource_mut::<InputMap>()
+        .0
+        .insert(Action::MoveRight, vec![Binding::Key(KeyCode::L)]);
+    let start = h.player().translation;
+    h.press(KeyCode::D);
+    h.run(30);
+    assert!((h.player().translation.x - start.x).abs() < 0.01);
+
+    h.release(KeyCode::D);
+    h.press(KeyCode::L);
+    h.run(30);
+    assert!(h.player().translation.x > start.x);
+}
+
+#[test]
+fn code_keypad_navigation() {
+    let mut h = Harness::rebuilt(1);
+    h.teleport("code.1");
+    h.hold(KeyCode::E, 1);
+    // Without a cursor the first move focuses the middle button.
+    h.hold(KeyCode::W, 1);
+    h.click();
+    let first = h.component::<Code>("code.1").input().to_string();
+    assert_eq!(first.len(), 1);
+
+    h.hold(KeyCode::D, 1);
+    h.click();
+    let input = h.component::<Code>("code.1").input().to_string();
+    assert_eq!(input.len(), 2);
+    assert_ne!(input[1..], first);
+}
+
+#[test]
+fn interaction_buffered() {
+    // A press shortly before reaching the switch still pulls it.
+    let mut h = switch(json!({}));
+    h.hold(KeyCode::E, 1);
+    h.teleport("switch.1");
+    h.run(60);
+    assert!(h.component::<Switch>("switch.1").activated());
+
+    // An old press does not.
+    let mut h = switch(json!({}));
+    h.hold(KeyCode::E, 1);
+    h.run(30);
+    h.teleport("switch.1");
+    h.run(60);
+    assert!(!h.component::<Switch>("switch.1").activated());
+}
+
+#[test]
+fn interaction_target() {
+    let target = |h: &mut Harness| h.app.world.resource::<InteractionTarget>().0;
+    let mut h = switch(json!({}));
+    assert_eq!(target(&mut h), None);
+    h.teleport("switch.1");
+    assert!(h.wait_until(30, |h| target(h).is_some()));
+
+    // A pulled switch cannot be used again.
+    h.hold(KeyCode::E, 2);
+    assert!(h.wait_until(30, |h| target(h).is_none()));
+    assert!(h.component::<Switch>("switch.1").activated());
+}
+
+#[test]
+fn oxygen_runs_out() {
+    let mut h = Harness::sandbox();
+    h.app.world.insert_resource(Oxygen::new(0.5));
+    h.run(10);
+    assert!(h.app.world.resource::<Oxygen>().left < 0.5);
+    assert!(
+        h.wait_until(120, |h| h.states.contains(&GameState::Restart)),
+        "Running out of oxygen did not restart the level"
+    );
+    assert!(h.wait_until(600, Harness::loaded), "Level did not reload");
+    assert_eq!(h.state(), GameState::Level(SANDBOX));
+}
+
+#[test]
+fn switch_modes() {
+    let mut h = switch(json!({ "mode": "toggle" }));
+    pull(&mut h, "switch.1");
+    h.run(30);
+    h.hold(KeyCode::E, 2);
+    assert!(
+        h.wait_until(60, |h| !h.component::<Switch>("switch.1").activated()),
+        "A toggle switch was not pushed back"
+    );
+
+    let mut h = switch(json!({ "mode": "timed", "duration": 1.0 }));
+    assert_eq!(h.component::<Switch>("switch.1").mode, SwitchMode::Timed);
+    pull(&mut h, "switch.1");
+    assert!(h.component::<Switch>("switch.1").remaining().is_some());
+    let switch = h.entity("switch.1");
+    let world = &mut h.app.world;
+    let countdown = world
+        .query::<(&SwitchCountdown, &Text)>()
+        .iter(world)
+        .find(|(countdown, _)| countdown.switch == switch)
+        .map(|(_, text)| text.sections[0].value.clone());
+    assert_eq!(
+        countdown.as_deref(),
+        Some("1"),
+        "No countdown of the seconds left"
+    );
+    assert!(
+        h.wait_until(120, |h| !h.component::<Switch>("switch.1").is_on()),
+        "A timed switch did not revert"
+    );
+}
+
+#[test]
+fn prop_grab() {
+    // The crate of the sandbox scene is a `dynamic` node with its mesh below it.
+    let mut h = Harness::sandbox();
+    let prop = h.entity("crate");
+    let world = &mut h.app.world;
+    let (collider, _) = world
+        .query::<(Entity, &PropCollider)>()
+        .iter(world)
+        .find(|(_, collider)| collider.0 == prop)
+        .expect("The crate has no collider to grab it by");
+    assert!(matches!(
+        world.get::<ColliderMassProperties>(collider),
+        Some(ColliderMassProperties::Mass(mass)) if *mass == 2.0
+    ));
+
+    let start = world.get::<GlobalTransform>(prop).unwrap().translation();
+    h.move_player(start.xy() - Vec2::X * 0.9);
+    assert!(
+        h.wait_until(30, |h| {
+            h.app.world.resource::<InteractionTarget>().0 == Some(collider)
+        }),
+        "The crate cannot be grabbed"
+    );
+    h.hold(KeyCode::E, 2);
+    assert!(h.app.world.get::<Prop>(prop).unwrap().grabbed);
+
+    h.press(KeyCode::W);
+    h.run(60);
+    h.release(KeyCode::W);
+    let position = h
+        .app
+        .world
+        .get::<GlobalTransform>(prop)
+        .unwrap()
+        .translation();
+    assert!(position.y > start.y + 1.0, "The crate was not carried");
+    assert!(position.xy().distance(h.player().translation.xy()) < 3.0);
+}
+
+#[test]
+fn broken_components_reported() {
+    let mut h = Harness::sandbox();
+    h.spawn_bare(
+        "switch.1",
+        "switch",
+        json!({}),
+        SANDBOX_SPAWN + Vec2::X * 5.0,
+    );
+    h.spawn_bare(
+        "camera.1",
+        "security_camera",
+        json!({}),
+        SANDBOX_SPAWN + Vec2::X * 10.0,
+    );
+    h.frame();
+
+    let errors = h
+        .app
+        .world
+        .resource::<SceneErrors>()
+        .0
+        .iter()
+        .map(|error| (error.node.as_str(), error.expected.as_str()))
+        .collect::<Vec<_>>();
+    assert!(
+        errors.contains(&("switch.1", "a \"sensor\" collider")),
+        "{errors:?}"
+    );
+    assert!(
+        errors.contains(&("camera.1", "a \"sensor\" collider, a \"cone\" mesh")),
+        "{errors:?}"
+    );
+    for name in ["switch.1", "camera.1"] {
+        let entity = h.entity(name);
+        assert!(h.app.world.get::<Disabled>(entity).is_some(), "{name}");
+    }
+}
+
+#[test]
+fn fan_spins_up_again() {
+    let mut h = Harness::sandbox();
+    h.spawn_component("fan.1", "fan", json!({}), SANDBOX_SPAWN + Vec2::X * 5.0);
+    // Its pusher is named like the fan, so it is taken for the fan's model.
+    let spin = |h: &mut Harness| {
+        let from = h.component::<Transform>("fan.1.pusher").rotation;
+        h.run(10);
+        h.component::<Transform>("fan.1.pusher")
+            .rotation
+            .angle_between(from)
+    };
+    assert!(spin(&mut h) > 0.0);
+
+    h.component_mut::<Fan>("fan.1").spinning = false;
+    h.run(120);
+    assert_eq!(spin(&mut h), 0.0, "The fan did not spin down");
+
+    h.component_mut::<Fan>("fan.1").spinning = true;
+    h.run(120);
+    assert!(spin(&mut h) > 0.0, "The fan did not spin up again");
+}